@@ -4,20 +4,168 @@
 // https://www.eclipse.org/legal/epl-2.0/
 // SPDX-License-Identifier: EPL-2.0
 
+use std::collections::{HashMap, HashSet};
 use std::env;
 
 use colored::Colorize;
 
 /// Это перечисление нужно для удобного распознавания действия которое передаётся
 /// вторым аргументом (индекс 1) при запуске spawn и означает "Что именно сделать?"
-/// Если там ничего нет либо такого варианта нет в match, то это help, то есть
-/// вывести справку по использованию spawn
+/// Если там ничего нет, то это help, то есть вывести справку по использованию spawn
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Actions {
     Help = 0,
     BuildApk = 1,
     BuildAab = 2,
     Up = 3,
     Clean = 4,
+    Check = 5,
+    Init = 6,
+    Completions = 7,
+    Lock = 8,
+}
+
+/// Описание флага команды. Если value равно None, то флаг булевый и значения
+/// не принимает, иначе это имя значения для справки (например <name>)
+pub struct Flag {
+    pub long: &'static str,
+    pub short: Option<char>,
+    pub value: Option<&'static str>,
+    pub help: &'static str,
+}
+
+/// Описание команды spawn: имя, позиционные аргументы для справки и флаги
+pub struct Command {
+    pub name: &'static str,
+    pub action: Actions,
+    pub usage: &'static str,
+    pub about: &'static str,
+    pub flags: &'static [Flag],
+}
+
+/// Флаги которые принимает любая команда
+pub const GLOBAL_FLAGS: &[Flag] = &[
+    Flag { long: "verbose", short: Some('v'), value: None, help: "print detailed output" },
+    Flag { long: "help", short: Some('h'), value: None, help: "show help for the command" },
+    Flag { long: "version", short: Some('V'), value: None, help: "show version" },
+];
+
+const VARIANT_FLAG: Flag = Flag { long: "variant", short: None, value: Some("name"), help: "apply [variants.<name>] from config" };
+
+const BUILD_FLAGS: &[Flag] = &[
+    Flag { long: "release", short: Some('r'), value: None, help: "build optimized release version (d8 --release, default)" },
+    Flag { long: "debug", short: Some('d'), value: None, help: "dex with debug info (d8 --debug instead of --release)" },
+    VARIANT_FLAG,
+    Flag { long: "jobs", short: Some('j'), value: Some("N"), help: "number of parallel downloads and build.ninja jobs" },
+    Flag { long: "offline", short: None, value: None, help: "resolve dependencies only from local cache" },
+];
+
+pub const COMMANDS: &[Command] = &[
+    Command {
+        name: "apk",
+        action: Actions::BuildApk,
+        usage: "[my.toml...]",
+        about: "build apk file",
+        flags: BUILD_FLAGS,
+    },
+    Command {
+        name: "aab",
+        action: Actions::BuildAab,
+        usage: "[my.toml...]",
+        about: "build aab file (for Google Play)",
+        flags: BUILD_FLAGS,
+    },
+    Command {
+        name: "check",
+        action: Actions::Check,
+        usage: "[my.toml...]",
+        about: "validate project without building",
+        flags: &[
+            Flag { long: "resolve", short: None, value: None, help: "also resolve dependency graph (caches POMs in ~/.spawn/repository)" },
+            VARIANT_FLAG,
+            Flag { long: "offline", short: None, value: None, help: "resolve dependencies only from local cache" },
+        ],
+    },
+    Command {
+        name: "lock",
+        action: Actions::Lock,
+        usage: "[my.toml...] [group:name...]",
+        about: "resolve dependencies and write spawn.lock",
+        flags: &[
+            Flag { long: "update", short: Some('u'), value: None, help: "re-resolve all (or only listed) artifacts" },
+            VARIANT_FLAG,
+            Flag { long: "offline", short: None, value: None, help: "resolve dependencies only from local cache" },
+        ],
+    },
+    Command {
+        name: "init",
+        action: Actions::Init,
+        usage: "[dir]",
+        about: "create new project",
+        flags: &[
+            Flag { long: "kotlin", short: None, value: None, help: "generate Kotlin sources instead of Java" },
+            Flag { long: "package", short: None, value: Some("name"), help: "application package, e.g. com.foo.bar" },
+            Flag { long: "modules", short: None, value: Some("list"), help: "extra modules, e.g. core,ui" },
+        ],
+    },
+    Command {
+        name: "up",
+        action: Actions::Up,
+        usage: "sdk|ndk",
+        about: "download/update android sdk/ndk",
+        flags: &[],
+    },
+    Command {
+        name: "clean",
+        action: Actions::Clean,
+        usage: "",
+        about: "delete all cache",
+        flags: &[],
+    },
+    Command {
+        name: "completions",
+        action: Actions::Completions,
+        usage: "bash|zsh|fish",
+        about: "print shell completion script",
+        flags: &[],
+    },
+    Command {
+        name: "help",
+        action: Actions::Help,
+        usage: "[command]",
+        about: "show help info",
+        flags: &[],
+    },
+];
+
+/// Результат разбора командной строки: действие, позиционные аргументы и флаги.
+/// Флаги хранятся по длинному имени, даже если были переданы коротким
+pub struct Cli {
+    pub action: Actions,
+    pub command: Option<&'static Command>,
+    pub args: Vec<String>,
+
+    switches: HashSet<&'static str>,
+    values: HashMap<&'static str, String>,
+}
+
+impl Cli {
+    pub fn flag(&self, long: &str) -> bool {
+        self.switches.contains(long)
+    }
+
+    pub fn value(&self, long: &str) -> Option<&str> {
+        self.values.get(long).map(|v| v.as_str())
+    }
+
+    /// Пути к toml файлам. Если ничего не передано, то используется ./spawn.toml
+    pub fn paths(&self) -> Vec<String> {
+        if self.args.is_empty() {
+            vec!["spawn.toml".to_string()]
+        } else {
+            self.args.clone()
+        }
+    }
 }
 
 /// Структура которая используется для глобального хранения аргументов системы
@@ -33,33 +181,229 @@ impl BuildSystem {
         }
     }
 
-    /// Эта функция берёт аргумент действия из args (Второй аргумент, первый индекс)
-    /// и возвращает либо help из Actions (0) либо конкретный вариант. Используется
-    /// enum для удобства обработки результата
-    pub fn get_action(&mut self) -> Actions {
-        if self.args.len() >= 2 {
-            return match self.args[1].as_str() {
-                "apk"   => Actions::BuildApk,
-                "aab"   => Actions::BuildAab,
-                "up"    => Actions::Up,
-                "clean" => Actions::Clean,
-                _       => Actions::Help,
+    /// Разбирает аргументы: второй аргумент (индекс 1) это команда, дальше идут
+    /// её флаги и позиционные аргументы в любом порядке. Всё после "--" считается
+    /// позиционным. Неизвестные команды и флаги это ошибка
+    pub fn parse(&self) -> Result<Cli, String> {
+        let mut cli = Cli {
+            action: Actions::Help,
+            command: None,
+            args: Vec::new(),
+            switches: HashSet::new(),
+            values: HashMap::new(),
+        };
+
+        let mut rest = self.args.iter().skip(1).peekable();
+
+        // Если аргументов меньше 2 то возвращаем help
+        let Some(first) = rest.peek() else {
+            return Ok(cli);
+        };
+
+        if first.starts_with('-') {
+            // spawn --version / spawn --help без команды
+            match first.as_str() {
+                "--version" | "-V" => {
+                    cli.switches.insert("version");
+                    return Ok(cli);
+                }
+                "--help" | "-h" => return Ok(cli),
+                _ => return Err(format!("unknown flag '{}'", first)),
             }
         }
 
-        // Если аргументов меньше 2 то возвращаем help
-        return Actions::Help;
+        let name = rest.next().unwrap();
+        let command = find_command(name)
+            .ok_or_else(|| format!("unknown command '{}'", name))?;
+
+        cli.action = command.action;
+        cli.command = Some(command);
+
+        let mut only_positional = false;
+
+        while let Some(arg) = rest.next() {
+            if only_positional || !arg.starts_with('-') || arg == "-" {
+                cli.args.push(arg.clone());
+                continue;
+            }
+
+            if arg == "--" {
+                only_positional = true;
+                continue;
+            }
+
+            // --name=value, --name, -x, -xVALUE
+            let (flag, inline) = if let Some(long) = arg.strip_prefix("--") {
+                let (key, inline) = match long.split_once('=') {
+                    Some((k, v)) => (k, Some(v.to_string())),
+                    None => (long, None),
+                };
+
+                (find_flag(command, |f| f.long == key), inline)
+            } else {
+                let mut chars = arg[1..].chars();
+                let short = chars.next();
+                let attached: String = chars.collect();
+                let inline = if attached.is_empty() { None } else { Some(attached) };
+
+                (find_flag(command, |f| f.short == short), inline)
+            };
+
+            let flag = flag.ok_or_else(|| format!("unknown flag '{}' for '{}'", arg, command.name))?;
+
+            match flag.value {
+                Some(_) => {
+                    let value = match inline {
+                        Some(v) => v,
+                        None => rest.next()
+                            .cloned()
+                            .ok_or_else(|| format!("flag '--{}' requires a value", flag.long))?,
+                    };
+
+                    cli.values.insert(flag.long, value);
+                }
+                None => {
+                    if inline.is_some() {
+                        return Err(format!("flag '--{}' does not take a value", flag.long));
+                    }
+
+                    cli.switches.insert(flag.long);
+                }
+            }
+        }
+
+        Ok(cli)
     }
 
-    /// [WAIT DOC]
-    pub fn print_help(&mut self) {
+    /// Общая справка со списком всех команд
+    pub fn print_help(&self) {
         println!("{} is easy-to-use build system for building android apps", "Spawn".green().bold());
-        println!("  - spawn apk my.toml     | build {} file", "apk".red());
-        println!("  - spawn aab my.toml     | build {} file (for Google Play)", "aab".red());
-        println!("  - spawn up sdk/ndk      | download/update android sdk/ndk");
-        println!("  - spawn clean           | delete all cache");
-        println!("  - spawn help            | show help info");
-        println!("If you want use multiconfig mode, use");
-        println!("  - spawn {} my.toml, my2.toml, my3.toml", "apk".red());
+        println!("Usage: spawn <command> [flags] [args]\n");
+        println!("Commands:");
+
+        for c in COMMANDS {
+            let usage = format!("{} {}", c.name, c.usage);
+            println!("  - spawn {:<20} | {}", usage.trim_end(), c.about);
+        }
+
+        println!("\nIf you want use multiconfig mode, use");
+        println!("  - spawn {} my.toml my2.toml my3.toml", "apk".red());
+        println!("Run 'spawn <command> --help' for command flags, 'spawn --version' for version");
     }
-}
\ No newline at end of file
+
+    /// Справка по конкретной команде и её флагам
+    pub fn print_command_help(&self, command: &Command) {
+        println!("{} {}", "spawn".green().bold(), command.name.red());
+        println!("  {}\n", command.about);
+        println!("Usage: spawn {} [flags] {}\n", command.name, command.usage);
+        println!("Flags:");
+
+        for f in command.flags.iter().chain(GLOBAL_FLAGS) {
+            let short = f.short.map(|c| format!("-{}, ", c)).unwrap_or_default();
+            let value = f.value.map(|v| format!(" <{}>", v)).unwrap_or_default();
+            let spec = format!("{}--{}{}", short, f.long, value);
+
+            println!("  {:<24} {}", spec, f.help);
+        }
+    }
+
+    pub fn print_version(&self) {
+        println!("spawn {}", env!("CARGO_PKG_VERSION"));
+    }
+}
+
+pub fn find_command(name: &str) -> Option<&'static Command> {
+    COMMANDS.iter().find(|c| c.name == name)
+}
+
+fn find_flag(command: &'static Command, pred: impl Fn(&Flag) -> bool) -> Option<&'static Flag> {
+    command.flags.iter().chain(GLOBAL_FLAGS).find(|f| pred(f))
+}
+
+/// group:name артефакта, например для `spawn lock --update`. Пути к файлам
+/// (в том числе C:\proj\spawn.toml) под это не подходят
+pub fn is_module_id(arg: &str) -> bool {
+    let valid = |part: &str| !part.is_empty()
+        && part.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'));
+
+    arg.split_once(':')
+        .is_some_and(|(group, name)| valid(group) && valid(name) && !name.ends_with(".toml"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Cli, String> {
+        let build_system = BuildSystem {
+            args: std::iter::once("spawn").chain(args.iter().copied()).map(String::from).collect(),
+        };
+
+        build_system.parse()
+    }
+
+    #[test]
+    fn unknown_flag() {
+        assert_eq!(parse(&["apk", "--nope"]).err().unwrap(), "unknown flag '--nope' for 'apk'");
+        assert_eq!(parse(&["apk", "-x"]).err().unwrap(), "unknown flag '-x' for 'apk'");
+        assert_eq!(parse(&["--nope"]).err().unwrap(), "unknown flag '--nope'");
+        assert_eq!(parse(&["init", "--offline"]).err().unwrap(), "unknown flag '--offline' for 'init'");
+        assert_eq!(parse(&["apk", "--offline=yes"]).err().unwrap(), "flag '--offline' does not take a value");
+        assert_eq!(parse(&["build"]).err().unwrap(), "unknown command 'build'");
+    }
+
+    #[test]
+    fn jobs_value() {
+        assert_eq!(parse(&["apk", "-j"]).err().unwrap(), "flag '--jobs' requires a value");
+        assert_eq!(parse(&["apk", "--jobs"]).err().unwrap(), "flag '--jobs' requires a value");
+
+        for args in [&["apk", "-j4"][..], &["apk", "-j", "4"], &["apk", "--jobs=4"], &["apk", "--jobs", "4"]] {
+            let cli = parse(args).unwrap();
+            assert_eq!(cli.value("jobs"), Some("4"), "{:?}", args);
+            assert!(cli.args.is_empty());
+        }
+    }
+
+    #[test]
+    fn default_path() {
+        let cli = parse(&["apk", "--release"]).unwrap();
+        assert_eq!(cli.action, Actions::BuildApk);
+        assert!(cli.flag("release"));
+        assert_eq!(cli.paths(), vec!["spawn.toml"]);
+
+        let cli = parse(&["apk", "a.toml", "--", "-b.toml"]).unwrap();
+        assert_eq!(cli.paths(), vec!["a.toml", "-b.toml"]);
+    }
+
+    #[test]
+    fn help_and_version() {
+        for command in COMMANDS {
+            let cli = parse(&[command.name, "--help"]).unwrap();
+            assert!(cli.flag("help"), "{}", command.name);
+            assert_eq!(cli.command.map(|c| c.name), Some(command.name));
+
+            assert!(parse(&[command.name, "-V"]).unwrap().flag("version"), "{}", command.name);
+        }
+
+        let cli = parse(&["--version"]).unwrap();
+        assert!(cli.flag("version"));
+        assert!(cli.command.is_none());
+
+        let cli = parse(&[]).unwrap();
+        assert_eq!(cli.action, Actions::Help);
+    }
+
+    #[test]
+    fn module_ids() {
+        assert!(is_module_id("androidx.core:core"));
+        assert!(is_module_id("com.squareup.okhttp3:okhttp"));
+
+        assert!(!is_module_id("spawn.toml"));
+        assert!(!is_module_id("C:\\proj\\spawn.toml.bak"));
+        assert!(!is_module_id("C:\\x\\app"));
+        assert!(!is_module_id("C:/x/app"));
+        assert!(!is_module_id("dir:app.toml"));
+        assert!(!is_module_id("a:b:c"));
+        assert!(!is_module_id(":b"));
+    }
+}
@@ -0,0 +1,326 @@
+// Copyright (c) 2025 Spawn
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// https://www.eclipse.org/legal/epl-2.0/
+// SPDX-License-Identifier: EPL-2.0
+
+use crate::build_system::{Command, Flag, COMMANDS, GLOBAL_FLAGS};
+use crate::parser;
+
+/// Скрипты получают имена вариантов у самого spawn в момент дополнения, так
+/// что список всегда совпадает с [variants.<name>] текущего spawn.toml
+const VARIANTS_COMMAND: &str = "spawn completions variants 2>/dev/null";
+
+/// Имена вариантов из ./spawn.toml для `spawn completions variants`. Ошибки
+/// конфига не выводятся, дополнение просто остаётся пустым
+pub fn variants() -> Vec<String> {
+    parser::load(vec!["spawn.toml".to_string()])
+        .map(|config| config.variant_names().into_iter().map(String::from).collect())
+        .unwrap_or_default()
+}
+
+/// Что дополнять на месте позиционных аргументов команды
+enum Positional {
+    TomlFiles,
+    Directories,
+    Commands,
+    Choices(Vec<&'static str>),
+    Nothing,
+}
+
+fn positional(command: &Command) -> Positional {
+    match command.usage {
+        u if u.contains(".toml") => Positional::TomlFiles,
+        "[dir]" => Positional::Directories,
+        "[command]" => Positional::Commands,
+        "" => Positional::Nothing,
+        u => Positional::Choices(u.split('|').collect()),
+    }
+}
+
+fn flags(command: &'static Command) -> impl Iterator<Item = &'static Flag> {
+    command.flags.iter().chain(GLOBAL_FLAGS)
+}
+
+/// Генерирует скрипт дополнения для bash, zsh или fish
+pub fn generate(shell: &str) -> Result<String, String> {
+    match shell {
+        "bash" => Ok(bash()),
+        "zsh" => Ok(zsh()),
+        "fish" => Ok(fish()),
+        _ => Err(format!("Unsupported shell '{}', expected bash, zsh or fish", shell)),
+    }
+}
+
+fn bash() -> String {
+    let names: Vec<&str> = COMMANDS.iter().map(|c| c.name).collect();
+    let value_flags: Vec<String> = COMMANDS.iter()
+        .flat_map(|c| c.flags.iter())
+        .filter(|f| f.value.is_some() && f.long != "variant")
+        .flat_map(|f| {
+            let mut v = vec![format!("--{}", f.long)];
+            v.extend(f.short.map(|s| format!("-{}", s)));
+            v
+        })
+        .collect();
+
+    let mut out = String::new();
+
+    out.push_str("# bash completion for spawn\n");
+    out.push_str("_spawn() {\n");
+    out.push_str("    local cur prev cmd flags\n");
+    out.push_str("    cur=\"${COMP_WORDS[COMP_CWORD]}\"\n");
+    out.push_str("    prev=\"${COMP_WORDS[COMP_CWORD-1]}\"\n");
+    out.push_str("    cmd=\"${COMP_WORDS[1]}\"\n\n");
+
+    out.push_str("    if [ \"$COMP_CWORD\" -eq 1 ]; then\n");
+    out.push_str(&format!(
+        "        COMPREPLY=( $(compgen -W \"{} --help --version\" -- \"$cur\") )\n",
+        names.join(" ")
+    ));
+    out.push_str("        return\n");
+    out.push_str("    fi\n\n");
+
+    out.push_str("    case \"$prev\" in\n");
+    out.push_str("        --variant)\n");
+    out.push_str(&format!("            COMPREPLY=( $(compgen -W \"$({})\" -- \"$cur\") )\n", VARIANTS_COMMAND));
+    out.push_str("            return ;;\n");
+
+    if !value_flags.is_empty() {
+        out.push_str(&format!("        {})\n", value_flags.join("|")));
+        out.push_str("            return ;;\n");
+    }
+
+    out.push_str("    esac\n\n");
+
+    out.push_str("    case \"$cmd\" in\n");
+
+    for c in COMMANDS {
+        let words: Vec<String> = flags(c)
+            .flat_map(|f| {
+                let mut v = vec![format!("--{}", f.long)];
+                v.extend(f.short.map(|s| format!("-{}", s)));
+                v
+            })
+            .collect();
+
+        out.push_str(&format!("        {})\n", c.name));
+        out.push_str(&format!("            flags=\"{}\"\n", words.join(" ")));
+        out.push_str("            if [[ \"$cur\" == -* ]]; then\n");
+        out.push_str("                COMPREPLY=( $(compgen -W \"$flags\" -- \"$cur\") )\n");
+        out.push_str("                return\n");
+        out.push_str("            fi\n");
+
+        match positional(c) {
+            Positional::TomlFiles => {
+                out.push_str("            COMPREPLY=( $(compgen -d -- \"$cur\") $(compgen -f -X '!*.toml' -- \"$cur\") )\n");
+            }
+            Positional::Directories => {
+                out.push_str("            COMPREPLY=( $(compgen -d -- \"$cur\") )\n");
+            }
+            Positional::Commands => {
+                out.push_str(&format!("            COMPREPLY=( $(compgen -W \"{}\" -- \"$cur\") )\n", names.join(" ")));
+            }
+            Positional::Choices(choices) => {
+                out.push_str(&format!("            COMPREPLY=( $(compgen -W \"{}\" -- \"$cur\") )\n", choices.join(" ")));
+            }
+            Positional::Nothing => {}
+        }
+
+        out.push_str("            ;;\n");
+    }
+
+    out.push_str("    esac\n");
+    out.push_str("}\n\n");
+    out.push_str("complete -o filenames -F _spawn spawn\n");
+
+    out
+}
+
+fn zsh_escape(s: &str) -> String {
+    s.replace('\'', "'\\''")
+        .replace('[', "\\[")
+        .replace(']', "\\]")
+        .replace(':', "\\:")
+}
+
+fn zsh() -> String {
+    let mut out = String::new();
+
+    out.push_str("#compdef spawn\n\n");
+
+    out.push_str("_spawn_variants() {\n");
+    out.push_str(&format!("    local -a variants\n    variants=(${{(f)\"$({})\"}})\n", VARIANTS_COMMAND));
+    out.push_str("    _describe -t variants 'variant' variants\n");
+    out.push_str("}\n\n");
+
+    out.push_str("_spawn_commands() {\n");
+    out.push_str("    local -a commands\n");
+    out.push_str("    commands=(\n");
+    for c in COMMANDS {
+        out.push_str(&format!("        '{}:{}'\n", c.name, zsh_escape(c.about)));
+    }
+    out.push_str("    )\n");
+    out.push_str("    _describe -t commands 'spawn command' commands\n");
+    out.push_str("}\n\n");
+
+    out.push_str("_spawn() {\n");
+    out.push_str("    local curcontext=\"$curcontext\" state line\n\n");
+    out.push_str("    _arguments -C \\\n");
+    out.push_str("        '(- :)--help[show help info]' \\\n");
+    out.push_str("        '(- :)--version[show version]' \\\n");
+    out.push_str("        '1: :_spawn_commands' \\\n");
+    out.push_str("        '*:: :->args'\n\n");
+    out.push_str("    case $state in\n");
+    out.push_str("        args)\n");
+    out.push_str("            case $line[1] in\n");
+
+    for c in COMMANDS {
+        let mut specs: Vec<String> = flags(c)
+            .map(|f| {
+                let action = match (f.long, f.value) {
+                    ("variant", Some(v)) => format!(":{}:_spawn_variants", v),
+                    (_, Some(v)) => format!(":{}: ", v),
+                    (_, None) => String::new(),
+                };
+
+                let spec = format!("[{}]{}", zsh_escape(f.help), action);
+
+                match f.short {
+                    Some(s) => format!("'(-{s} --{l})'{{-{s},--{l}}}'{spec}'", s = s, l = f.long, spec = spec),
+                    None => format!("'--{}{}'", f.long, spec),
+                }
+            })
+            .collect();
+
+        match positional(c) {
+            Positional::TomlFiles => specs.push("'*:config:_files -g \"*.toml\"'".to_string()),
+            Positional::Directories => specs.push("'1:directory:_files -/'".to_string()),
+            Positional::Commands => specs.push("'1: :_spawn_commands'".to_string()),
+            Positional::Choices(choices) => specs.push(format!("'1:{}:({})'", c.name, choices.join(" "))),
+            Positional::Nothing => {}
+        }
+
+        out.push_str(&format!("                {})\n", c.name));
+        out.push_str("                    _arguments \\\n");
+        out.push_str(&format!("                        {}\n", specs.join(" \\\n                        ")));
+        out.push_str("                    ;;\n");
+    }
+
+    out.push_str("            esac\n");
+    out.push_str("            ;;\n");
+    out.push_str("    esac\n");
+    out.push_str("}\n\n");
+    out.push_str("_spawn \"$@\"\n");
+
+    out
+}
+
+fn fish_escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"").replace('$', "\\$")
+}
+
+fn fish() -> String {
+    let names: Vec<&str> = COMMANDS.iter().map(|c| c.name).collect();
+    let mut out = String::new();
+
+    out.push_str("# fish completion for spawn\n");
+    out.push_str("complete -c spawn -f\n");
+    out.push_str("complete -c spawn -n \"__fish_use_subcommand\" -l help -d \"show help info\"\n");
+    out.push_str("complete -c spawn -n \"__fish_use_subcommand\" -l version -d \"show version\"\n");
+
+    for c in COMMANDS {
+        out.push_str(&format!(
+            "complete -c spawn -n \"__fish_use_subcommand\" -a {} -d \"{}\"\n",
+            c.name,
+            fish_escape(c.about)
+        ));
+    }
+
+    out.push('\n');
+
+    for c in COMMANDS {
+        let cond = format!("-n \"__fish_seen_subcommand_from {}\"", c.name);
+
+        for f in flags(c) {
+            let short = f.short.map(|s| format!(" -s {}", s)).unwrap_or_default();
+
+            let value = match (f.long, f.value) {
+                ("variant", Some(_)) => format!(" -x -a \"({})\"", VARIANTS_COMMAND),
+                (_, Some(_)) => " -x".to_string(),
+                (_, None) => String::new(),
+            };
+
+            out.push_str(&format!(
+                "complete -c spawn {} -l {}{}{} -d \"{}\"\n",
+                cond,
+                f.long,
+                short,
+                value,
+                fish_escape(f.help)
+            ));
+        }
+
+        match positional(c) {
+            Positional::TomlFiles => {
+                out.push_str(&format!("complete -c spawn {} -a \"(__fish_complete_suffix .toml)\"\n", cond));
+            }
+            Positional::Directories => {
+                out.push_str(&format!("complete -c spawn {} -a \"(__fish_complete_directories)\"\n", cond));
+            }
+            Positional::Commands => {
+                out.push_str(&format!("complete -c spawn {} -a \"{}\"\n", cond, names.join(" ")));
+            }
+            Positional::Choices(choices) => {
+                out.push_str(&format!("complete -c spawn {} -a \"{}\"\n", cond, choices.join(" ")));
+            }
+            Positional::Nothing => {}
+        }
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scripts_cover_commands_and_flags() {
+        for shell in ["bash", "zsh", "fish"] {
+            let script = generate(shell).unwrap();
+
+            for c in COMMANDS {
+                assert!(script.contains(c.name), "{}: no command {}", shell, c.name);
+
+                for f in flags(c) {
+                    let long = match shell {
+                        "fish" => format!("-l {}", f.long),
+                        _ => format!("--{}", f.long),
+                    };
+
+                    assert!(script.contains(&long), "{}: no {} for {}", shell, long, c.name);
+
+                    if let Some(short) = f.short {
+                        let short = match shell {
+                            "fish" => format!("-s {}", short),
+                            _ => format!("-{}", short),
+                        };
+
+                        assert!(script.contains(&short), "{}: no {} for {}", shell, short, c.name);
+                    }
+                }
+            }
+
+            assert!(script.contains(VARIANTS_COMMAND), "{}: no variant completion", shell);
+        }
+    }
+
+    #[test]
+    fn unknown_shell() {
+        assert_eq!(
+            generate("powershell").unwrap_err(),
+            "Unsupported shell 'powershell', expected bash, zsh or fish"
+        );
+    }
+}
@@ -0,0 +1,253 @@
+// Copyright (c) 2025 Spawn
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// https://www.eclipse.org/legal/epl-2.0/
+// SPDX-License-Identifier: EPL-2.0
+
+use std::env;
+use std::path::{Path, PathBuf};
+
+use walkdir::WalkDir;
+
+use crate::parser;
+
+/// Отчёт проверки проекта. Проверка не останавливается на первой проблеме,
+/// а собирает всё что нашла, чтобы пользователь увидел полный список
+#[derive(Debug, Default)]
+pub struct CheckReport {
+    pub errors: Vec<String>,
+    pub warnings: Vec<String>,
+}
+
+impl CheckReport {
+    pub fn is_ok(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    pub fn print(&self) {
+        for w in &self.warnings {
+            warn!("{}", w);
+        }
+
+        for e in &self.errors {
+            error!("{}", e);
+        }
+    }
+}
+
+/// Проверяет проект без сборки: конфиг, манифест, исходники модулей, наличие
+/// платформы SDK и (если resolve = true) граф зависимостей. Ничего не пишет
+/// в .spawn проекта, поэтому подходит для pre-commit хуков. Разрешение графа
+/// при этом скачивает pom и maven-metadata.xml в глобальный кэш
+/// ~/.spawn/repository, как и обычная сборка
+pub fn check(paths: Vec<String>, options: &super::BuildOptions, resolve: bool) -> CheckReport {
+    let mut report = CheckReport::default();
+
+    task!("Checking project");
+
+    let config = match super::load_config(paths, options) {
+        Ok(c) => c,
+        Err(e) => {
+            report.errors.push(format!("Failed to load config: {}", e));
+            return report;
+        }
+    };
+
+    check_manifest(&config, &mut report);
+    check_modules(&config, &mut report);
+    check_sdk(&config, sdk_root().as_deref(), &mut report);
+
+    if resolve {
+        check_dependencies(&config, options, &mut report);
+    }
+
+    report
+}
+
+fn check_manifest(config: &parser::Config, report: &mut CheckReport) {
+    let manifest_path = config.base_path.join("AndroidManifest.xml");
+
+    if !manifest_path.exists() {
+        report.errors.push(format!("AndroidManifest.xml not found {:?}", manifest_path));
+        return;
+    }
+
+    // Манифест генерируется только в память, в кэш он не сохраняется
+    if let Err(e) = parser::generate_manifest(&manifest_path, config) {
+        report.errors.push(format!("Invalid AndroidManifest.xml: {}", e));
+    }
+
+    if config.package.as_ref().and_then(|p| p.package.as_ref()).is_none() {
+        report.warnings.push("No package name in [package] section".to_string());
+    }
+}
+
+fn check_modules(config: &parser::Config, report: &mut CheckReport) {
+    for (index, module_path) in config.modules.iter().enumerate() {
+        // Корневой конфиг сам по себе может не содержать исходников если у
+        // проекта есть модули
+        if index == 0 && config.modules.len() > 1 {
+            continue;
+        }
+
+        let module_dir = module_path.parent().unwrap_or(Path::new("."));
+
+        if !has_sources(&module_dir.join("java"), "java") && !has_sources(&module_dir.join("kotlin"), "kt") {
+            report.errors.push(format!(
+                "Module {} has no sources (expected java/ or kotlin/ directory)",
+                module_path.display()
+            ));
+        }
+    }
+}
+
+fn has_sources(dir: &Path, ext: &str) -> bool {
+    dir.exists() && WalkDir::new(dir)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .any(|e| e.path().extension().is_some_and(|e| e == ext))
+}
+
+fn sdk_root() -> Option<PathBuf> {
+    env::var_os("ANDROID_HOME")
+        .or_else(|| env::var_os("ANDROID_SDK_ROOT"))
+        .map(PathBuf::from)
+}
+
+fn check_sdk(config: &parser::Config, sdk_root: Option<&Path>, report: &mut CheckReport) {
+    let target_sdk = config.package.as_ref()
+        .and_then(|p| p.target_sdk)
+        .unwrap_or(34);
+
+    let Some(sdk_root) = sdk_root else {
+        report.errors.push("ANDROID_HOME is not set".to_string());
+        return;
+    };
+
+    let platform = sdk_root.join("platforms").join(format!("android-{}", target_sdk));
+
+    if !platform.join("android.jar").exists() {
+        report.errors.push(format!(
+            "SDK platform android-{} not found in {}",
+            target_sdk,
+            sdk_root.display()
+        ));
+    }
+}
+
+fn check_dependencies(config: &parser::Config, options: &super::BuildOptions, report: &mut CheckReport) {
+    if let Err(e) = super::libraries::local_libraries(config) {
+        report.errors.push(e);
+    }
+
+    let root_deps = super::root_coords(config);
+
+    if root_deps.is_empty() {
+        return;
+    }
+
+    let mut r = match super::new_resolver(config, options) {
+        Ok(r) => r,
+        Err(e) => {
+            report.errors.push(e);
+            return;
+        }
+    };
+
+    // Только граф, без скачивания aar/jar. pom и метаданные всё равно
+    // попадают в ~/.spawn/repository
+    r.resolve(root_deps);
+
+    for failure in &r.unresolved {
+        report.errors.push(format!("Unresolved dependency {}", failure));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    /// Проект во временной папке: spawn.toml и файлы по относительным путям
+    fn project(name: &str, files: &[(&str, &str)]) -> PathBuf {
+        let dir = env::temp_dir().join(format!("spawn-test-check-{}-{}", name, std::process::id()));
+        fs::remove_dir_all(&dir).ok();
+
+        for (path, content) in files {
+            let path = dir.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        }
+
+        dir
+    }
+
+    fn load(dir: &Path) -> parser::Config {
+        parser::load(vec![dir.join("spawn.toml").display().to_string()]).unwrap()
+    }
+
+    const SPAWN_TOML: &str = "[package]\npackage = \"com.example.app\"\ntarget_sdk = 34\n";
+    const MANIFEST: &str = "<manifest xmlns:android=\"http://schemas.android.com/apk/res/android\">\
+        <application></application></manifest>";
+
+    #[test]
+    fn missing_android_jar() {
+        let dir = project("sdk", &[("spawn.toml", SPAWN_TOML), ("sdk/platforms/android-33/android.jar", "")]);
+        let config = load(&dir);
+
+        let mut report = CheckReport::default();
+        check_sdk(&config, Some(&dir.join("sdk")), &mut report);
+
+        let mut unset = CheckReport::default();
+        check_sdk(&config, None, &mut unset);
+
+        fs::remove_dir_all(&dir).ok();
+
+        assert_eq!(report.errors.len(), 1);
+        assert!(report.errors[0].starts_with("SDK platform android-34 not found"));
+        assert_eq!(unset.errors, vec!["ANDROID_HOME is not set"]);
+    }
+
+    #[test]
+    fn module_without_sources() {
+        let dir = project("sources", &[
+            ("spawn.toml", SPAWN_TOML),
+            ("app/module.toml", ""),
+            ("app/java/com/example/Main.java", "class Main {}"),
+            ("core/module.toml", ""),
+            ("core/java/README.md", ""),
+        ]);
+
+        let mut report = CheckReport::default();
+        check_modules(&load(&dir), &mut report);
+
+        fs::remove_dir_all(&dir).ok();
+
+        assert_eq!(report.errors.len(), 1);
+        assert!(report.errors[0].contains("core"), "{:?}", report.errors);
+        assert!(report.errors[0].contains("has no sources"));
+    }
+
+    #[test]
+    fn invalid_manifest() {
+        let valid = project("manifest-ok", &[("spawn.toml", SPAWN_TOML), ("AndroidManifest.xml", MANIFEST)]);
+        let invalid = project("manifest-bad", &[
+            ("spawn.toml", SPAWN_TOML),
+            ("AndroidManifest.xml", "<manifest><application></manifest>"),
+        ]);
+
+        let mut ok = CheckReport::default();
+        check_manifest(&load(&valid), &mut ok);
+
+        let mut bad = CheckReport::default();
+        check_manifest(&load(&invalid), &mut bad);
+
+        fs::remove_dir_all(&valid).ok();
+        fs::remove_dir_all(&invalid).ok();
+
+        assert!(ok.is_ok(), "{:?}", ok.errors);
+        assert_eq!(bad.errors.len(), 1);
+        assert!(bad.errors[0].starts_with("Invalid AndroidManifest.xml"), "{:?}", bad.errors);
+    }
+}
@@ -0,0 +1,241 @@
+// Copyright (c) 2025 Spawn
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// https://www.eclipse.org/legal/epl-2.0/
+// SPDX-License-Identifier: EPL-2.0
+
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Параметры создания нового проекта через spawn init
+pub struct InitOptions {
+    pub dir: PathBuf,
+    pub package: String,
+    pub kotlin: bool,
+
+    /// Дополнительные модули помимо main
+    pub modules: Vec<String>,
+}
+
+/// Создаёт каркас проекта: spawn.toml, AndroidManifest.xml, .gitignore и модули
+/// с module.toml. Существующие файлы никогда не перезаписываются, если хотя бы
+/// один из них уже есть, то ничего не создаётся. Возвращает список созданных файлов
+pub fn init(options: &InitOptions) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+    validate_package(&options.package)?;
+
+    for module in &options.modules {
+        validate_module(module)?;
+    }
+
+    let files = project_files(options);
+
+    let existing: Vec<String> = files.iter()
+        .filter(|(path, _)| path.exists())
+        .map(|(path, _)| path.display().to_string())
+        .collect();
+
+    if !existing.is_empty() {
+        return Err(format!("Refusing to overwrite existing files: {}", existing.join(", ")).into());
+    }
+
+    for (path, content) in &files {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        fs::write(path, content)?;
+    }
+
+    Ok(files.into_iter().map(|(path, _)| path).collect())
+}
+
+fn project_files(options: &InitOptions) -> Vec<(PathBuf, String)> {
+    let dir = &options.dir;
+    let label = project_label(dir);
+
+    let mut files = vec![
+        (dir.join("spawn.toml"), spawn_toml(&options.package, &label)),
+        (dir.join("AndroidManifest.xml"), android_manifest()),
+        (dir.join(".gitignore"), ".spawn/\n".to_string()),
+    ];
+
+    let main_dir = dir.join("main");
+    files.push((main_dir.join("module.toml"), module_toml()));
+    files.push((main_dir.join("res").join("values").join("strings.xml"), strings_xml(&label)));
+    files.push(source_file(&main_dir, &options.package, "MainActivity", options.kotlin, main_activity));
+
+    for module in &options.modules {
+        if module == "main" {
+            continue;
+        }
+
+        let module_dir = dir.join(module);
+        let module_package = format!("{}.{}", options.package, module);
+        let class_name = class_name(module);
+
+        files.push((module_dir.join("module.toml"), module_toml()));
+        files.push(source_file(&module_dir, &module_package, &class_name, options.kotlin, empty_class));
+    }
+
+    files
+}
+
+/// Путь и содержимое исходника. Java кладётся в java/, Kotlin в kotlin/ как
+/// ожидает ninja_generator
+fn source_file(
+    module_dir: &Path,
+    package: &str,
+    class: &str,
+    kotlin: bool,
+    template: fn(&str, &str, bool) -> String,
+) -> (PathBuf, String) {
+    let (lang_dir, ext) = if kotlin { ("kotlin", "kt") } else { ("java", "java") };
+
+    let mut path = module_dir.join(lang_dir);
+    for segment in package.split('.') {
+        path.push(segment);
+    }
+    path.push(format!("{}.{}", class, ext));
+
+    (path, template(package, class, kotlin))
+}
+
+fn validate_package(package: &str) -> Result<(), Box<dyn Error>> {
+    let segments: Vec<&str> = package.split('.').collect();
+
+    let valid = segments.len() >= 2 && segments.iter().all(|s| is_identifier(s));
+
+    if !valid {
+        return Err(format!("Invalid package name '{}', expected something like com.example.app", package).into());
+    }
+
+    Ok(())
+}
+
+fn validate_module(module: &str) -> Result<(), Box<dyn Error>> {
+    if !is_identifier(module) {
+        return Err(format!("Invalid module name '{}'", module).into());
+    }
+
+    Ok(())
+}
+
+fn is_identifier(s: &str) -> bool {
+    let mut chars = s.chars();
+
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' => {}
+        _ => return false,
+    }
+
+    chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Имя приложения по умолчанию берётся из названия папки проекта
+fn project_label(dir: &Path) -> String {
+    // Папки может ещё не быть, тогда canonicalize не сработает
+    fs::canonicalize(dir)
+        .unwrap_or_else(|_| dir.to_path_buf())
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "My App".to_string())
+}
+
+fn class_name(module: &str) -> String {
+    module.split('_')
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let mut chars = part.chars();
+            match chars.next() {
+                Some(c) => c.to_ascii_uppercase().to_string() + chars.as_str(),
+                None => String::new(),
+            }
+        })
+        .collect()
+}
+
+fn spawn_toml(package: &str, label: &str) -> String {
+    format!(r#"repositories = [
+    "https://maven.google.com",
+    "https://repo1.maven.org/maven2"
+]
+
+[package]
+package = "{package}"
+version = "1.0.0"
+version_code = 1
+label = "{label}"
+min_sdk = 21
+target_sdk = 34
+
+# [dependencies]
+# "androidx.appcompat:appcompat" = "1.6.1"
+"#)
+}
+
+fn module_toml() -> String {
+    "# [dependencies]\n".to_string()
+}
+
+fn android_manifest() -> String {
+    r#"<?xml version="1.0" encoding="utf-8"?>
+<manifest xmlns:android="http://schemas.android.com/apk/res/android">
+    <application android:label="@string/app_name">
+        <activity
+            android:name=".MainActivity"
+            android:exported="true">
+            <intent-filter>
+                <action android:name="android.intent.action.MAIN" />
+                <category android:name="android.intent.category.LAUNCHER" />
+            </intent-filter>
+        </activity>
+    </application>
+</manifest>
+"#.to_string()
+}
+
+fn strings_xml(label: &str) -> String {
+    format!(r#"<?xml version="1.0" encoding="utf-8"?>
+<resources>
+    <string name="app_name">{label}</string>
+</resources>
+"#)
+}
+
+fn main_activity(package: &str, class: &str, kotlin: bool) -> String {
+    if kotlin {
+        format!(r#"package {package}
+
+import android.app.Activity
+import android.os.Bundle
+
+class {class} : Activity() {{
+    override fun onCreate(savedInstanceState: Bundle?) {{
+        super.onCreate(savedInstanceState)
+    }}
+}}
+"#)
+    } else {
+        format!(r#"package {package};
+
+import android.app.Activity;
+import android.os.Bundle;
+
+public class {class} extends Activity {{
+    @Override
+    protected void onCreate(Bundle savedInstanceState) {{
+        super.onCreate(savedInstanceState);
+    }}
+}}
+"#)
+    }
+}
+
+fn empty_class(package: &str, class: &str, kotlin: bool) -> String {
+    if kotlin {
+        format!("package {package}\n\nobject {class}\n")
+    } else {
+        format!("package {package};\n\npublic final class {class} {{\n}}\n")
+    }
+}
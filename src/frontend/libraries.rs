@@ -0,0 +1,87 @@
+// Copyright (c) 2025 Spawn
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// https://www.eclipse.org/legal/epl-2.0/
+// SPDX-License-Identifier: EPL-2.0
+
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::parser;
+use crate::parser::toml_parser::Substitution;
+use crate::resolver::graph::Scope;
+use crate::resolver::unpacker::unpack_aar_into;
+
+/// Файлы path зависимостей со своим scope: { path = "libs/foo.aar" } это
+/// один файл, { path = "libs" } все .jar и .aar прямо в папке (без
+/// вложенных). Сюда же входят локальные файлы из [substitutions]
+pub fn local_libraries(config: &parser::Config) -> Result<Vec<(PathBuf, Scope)>, String> {
+    let mut deps: Vec<(&String, &str, Scope)> = config.dependencies.iter()
+        .flatten()
+        .filter_map(|(name, dep)| Some((name, dep.path()?, dep.scope().scope())))
+        .collect();
+
+    for (name, substitution) in config.substitutions.iter().flatten() {
+        if let Substitution::Local(local) = substitution {
+            deps.push((name, &local.path, Scope::Compile));
+        }
+    }
+
+    deps.sort_by_key(|d| d.0);
+
+    let mut files = Vec::new();
+
+    for (name, path, scope) in deps {
+        let path = Path::new(path);
+
+        if path.is_dir() {
+            let mut found: Vec<PathBuf> = fs::read_dir(path)
+                .map_err(|e| format!("Dependency \"{}\": {}: {}", name, path.display(), e))?
+                .filter_map(|e| e.ok())
+                .map(|e| e.path())
+                .filter(|p| p.is_file() && is_library(p))
+                .collect();
+
+            if found.is_empty() {
+                warn!("Dependency \"{}\": no .jar or .aar files in {}", name, path.display());
+            }
+
+            found.sort();
+            files.extend(found.into_iter().map(|f| (f, scope)));
+        } else if !path.exists() {
+            return Err(format!("Dependency \"{}\": {} does not exist", name, path.display()));
+        } else if !is_library(path) {
+            return Err(format!("Dependency \"{}\": {} is not a .jar or .aar", name, path.display()));
+        } else {
+            files.push((path.to_path_buf(), scope));
+        }
+    }
+
+    Ok(files)
+}
+
+/// Jar файлы path зависимостей и их scope. Aar распаковываются в
+/// .spawn/cache/libs/<имя файла>, вместо них идёт их classes.jar
+pub fn jars(config: &parser::Config) -> Result<Vec<(PathBuf, Scope)>, Box<dyn Error>> {
+    let libs_dir = config.base_path.join(".spawn").join("cache").join("libs");
+    let mut jars = Vec::new();
+
+    for (lib, scope) in local_libraries(config)? {
+        if lib.extension().is_some_and(|e| e == "aar") {
+            let name = lib.file_name().unwrap_or_default();
+            let unpacked = unpack_aar_into(&lib, &libs_dir.join(name))
+                .map_err(|e| format!("unpack error {}: {}", lib.display(), e))?;
+
+            jars.push((unpacked.join("classes.jar"), scope));
+        } else {
+            jars.push((lib, scope));
+        }
+    }
+
+    Ok(jars)
+}
+
+fn is_library(path: &Path) -> bool {
+    path.extension().is_some_and(|e| e == "jar" || e == "aar")
+}
@@ -4,77 +4,403 @@
 // https://www.eclipse.org/legal/epl-2.0/
 // SPDX-License-Identifier: EPL-2.0
 
+pub mod check;
+pub mod init;
+pub mod libraries;
 pub mod manifest;
 pub mod ninja_generator;
 
-use std::fs;
+use std::time::Duration;
+
 use sha2::{Sha256, Digest};
 use colored::Colorize;
 use dirs::home_dir;
 
 use crate::parser;
-use crate::resolver::Resolver;
+use crate::parser::toml_parser::{DefaultRepositories, DependencyScope, RepositoryConfig, Substitution};
+use crate::parser::user_config;
+use crate::resolver::{error, Resolver};
+use crate::resolver::artifact::Artifact;
+use crate::resolver::graph::{ConflictStrategy, Exclusion};
+use crate::resolver::http::HttpSettings;
+use crate::resolver::lockfile::{LockFile, LOCK_FILE};
+use crate::resolver::repository::{Mirror, Repository, DEFAULT_REPOSITORIES};
+use crate::resolver::signature::{SignaturePolicy, SignatureVerifier};
+use crate::resolver::version::VersionRange;
+
+/// Файл с доверенными PGP ключами если в [verification] не указан другой
+const TRUSTED_KEYS_FILE: &str = "trusted-keys.asc";
+
+/// Имя репозитория ~/.m2/repository, под ним он виден в include/exclude и ошибках
+const MAVEN_LOCAL: &str = "mavenLocal";
+
+/// Настройки сборки которые приходят из флагов командной строки
+#[derive(Debug, Default, Clone)]
+pub struct BuildOptions {
+    /// d8 --debug вместо --release
+    pub debug: bool,
+
+    pub offline: bool,
+
+    /// Имя из [variants.<name>]
+    pub variant: Option<String>,
+
+    /// Размер пула jobs в build.ninja, None если не ограничен
+    pub jobs: Option<usize>,
+}
+
+/// Загружает конфиг и применяет к нему вариант сборки если он выбран
+pub fn load_config(paths: Vec<String>, options: &BuildOptions) -> Result<parser::Config, Box<dyn std::error::Error>> {
+    let mut config = parser::load(paths)?;
+
+    if let Some(variant) = &options.variant {
+        config.apply_variant(variant)?;
+    }
+
+    Ok(config)
+}
+
+/// Как обращаться с spawn.lock при разрешении зависимостей
+#[derive(Debug, Clone)]
+pub enum LockMode {
+    /// Использовать lock если он актуален, иначе разрешить граф и записать новый
+    Use,
+
+    /// Разрешить весь граф заново
+    UpdateAll,
+
+    /// Разрешить заново только указанные group:name, остальное как в lock
+    Update(Vec<String>),
+}
 
 /// Подготавливает проект и возвращает конфиг + resolver (если были зависимости)
-pub fn prepare(paths: Vec<String>) -> Result<(parser::Config, Option<Resolver>), Box<dyn std::error::Error>> {
-    let config = parser::load(paths)?;
+pub fn prepare(paths: Vec<String>, options: &BuildOptions) -> Result<(parser::Config, Option<Resolver>), Box<dyn std::error::Error>> {
+    let config = load_config(paths, options)?;
     manifest::prepare_manifest(&config)?;
 
-    let mut resolver = None;
+    let resolver = resolve_dependencies(&config, options, &LockMode::Use)?;
 
-    if let Some(deps) = &config.dependencies {
-        if !deps.is_empty() {
-            let project_cache_dir = config.base_path.join(".spawn").join("cache");
-            let lock_file = project_cache_dir.join("resolve.lock");
-
-            let current_fingerprint = generate_fingerprint(&config);
-
-            let mut need_resolve = true;
-            if lock_file.exists() {
-                if let Ok(saved) = fs::read_to_string(&lock_file) {
-                    if saved.trim() == current_fingerprint {
-                        need_resolve = false;
-                        info!("{} Dependencies are up-to-date", "CACHED:".green());
-                    }
-                }
+    Ok((config, resolver))
+}
+
+/// spawn lock: разрешает зависимости и записывает spawn.lock без сборки
+pub fn lock(paths: Vec<String>, options: &BuildOptions, mode: &LockMode) -> Result<(), Box<dyn std::error::Error>> {
+    let config = load_config(paths, options)?;
+    resolve_dependencies(&config, options, mode)?;
+
+    Ok(())
+}
+
+fn resolve_dependencies(
+    config: &parser::Config,
+    options: &BuildOptions,
+    mode: &LockMode,
+) -> Result<Option<Resolver>, Box<dyn std::error::Error>> {
+    let root_deps = root_coords(config);
+
+    if root_deps.is_empty() {
+        info!("No dependencies section — building without external libs");
+        return Ok(None);
+    }
+
+    let lock_path = config.base_path.join(LOCK_FILE);
+    let current_fingerprint = generate_fingerprint(config);
+    let lock = LockFile::load(&lock_path)?;
+
+    let mut r = new_resolver(config, options)?;
+
+    // Lock используется как есть только если он совпадает с конфигом
+    let locked = match (mode, &lock) {
+        (LockMode::Use, Some(l)) if l.fingerprint == current_fingerprint => Some(l),
+        (LockMode::Use, Some(_)) => {
+            note!("{} is out of date, resolving dependencies", LOCK_FILE);
+            None
+        },
+        _ => None,
+    };
+
+    if let Some(l) = locked {
+        info!("{} Using {}", "CACHED:".green(), LOCK_FILE);
+        r.load_locked(&l.artifacts);
+    } else {
+        task!("Resolving dependencies");
+
+        if let LockMode::Update(ids) = mode {
+            let Some(l) = &lock else {
+                return Err(format!("No {} to update, run `spawn lock` first", LOCK_FILE).into());
+            };
+
+            r.pinned = l.pins_except(ids)?;
+            r.prefer_sources(&l.artifacts);
+        }
+
+        r.resolve(root_deps);
+    }
+
+    r.download_all();
+
+    // Без любого из артефактов собирать нечего, поэтому это ошибка
+    if !r.unresolved.is_empty() {
+        return Err(failure_report(&r, options).into());
+    }
+
+    // До записи spawn.lock, чтобы в нём не оказались непроверенные артефакты
+    verify_signatures(config, &r)?;
+
+    if let Some(l) = locked {
+        let mismatches = r.verify_locked(&l.artifacts);
+
+        if !mismatches.is_empty() {
+            return Err(format!(
+                "Artifacts do not match {}:\n  - {}",
+                LOCK_FILE,
+                mismatches.join("\n  - ")
+            ).into());
+        }
+    } else if r.verify_all_artifacts_exist() {
+        LockFile::from_resolver(&r, current_fingerprint)?.save(&lock_path)?;
+        info!("Dependencies resolved, {} written", LOCK_FILE);
+    } else {
+        warn!("Some dependencies failed to download, {} was not updated", LOCK_FILE);
+    }
+
+    Ok(Some(r))
+}
+
+/// Проверка PGP подписей если она включена в [verification]. В режиме warn
+/// проблемы только выводятся, в strict сборка останавливается
+fn verify_signatures(config: &parser::Config, r: &Resolver) -> Result<(), Box<dyn std::error::Error>> {
+    let Some(verification) = &config.verification else {
+        return Ok(());
+    };
+
+    let policy = verification.signatures.unwrap_or_default();
+
+    if policy == SignaturePolicy::Off {
+        return Ok(());
+    }
+
+    let keys = config.base_path.join(verification.keys.as_deref().unwrap_or(TRUSTED_KEYS_FILE));
+    let verifier = SignatureVerifier::new(&keys, &verification.trusted.clone().unwrap_or_default())?;
+    let report = r.verify_signatures(&verifier);
+
+    let problems: Vec<String> = report.problems()
+        .map(|e| format!("{} ({}): {}", e.artifact, e.file.file_name().unwrap_or_default().to_string_lossy(), e.status))
+        .collect();
+
+    if problems.is_empty() {
+        info!("All {} signatures are trusted", report.entries.len());
+        return Ok(());
+    }
+
+    match policy {
+        SignaturePolicy::Strict => Err(format!(
+            "{} file(s) failed signature verification:\n  - {}",
+            problems.len(),
+            problems.join("\n  - ")
+        ).into()),
+
+        _ => {
+            for p in &problems {
+                warn!("{}", p);
             }
 
-            if need_resolve {
-                task!("Resolving dependencies");
+            warn!("{} of {} file(s) are unsigned or untrusted", problems.len(), report.entries.len());
+            Ok(())
+        }
+    }
+}
 
-                let home = home_dir().expect("Cannot find home directory");
-                let global_repository = home.join(".spawn").join("repository");
+/// Создаёт resolver с глобальным кэшем ~/.spawn и настройками из конфига и флагов
+fn new_resolver(config: &parser::Config, options: &BuildOptions) -> Result<Resolver, String> {
+    let home = home_dir().expect("Cannot find home directory");
+    let global_repository = home.join(".spawn").join("repository");
 
-                let mut r = Resolver::new(
-                    config.repositories.clone().unwrap_or_default(),
-                    &global_repository,
-                );
+    let user = user_config::load().map_err(|e| e.to_string())?;
+    let repositories = repositories(config)?;
 
-                let root_deps: Vec<String> = deps.iter()
-                    .map(|(k, v)| format!("{}:{}", k, v))
-                    .collect();
+    let mut r = Resolver::new(repositories, &global_repository);
 
-                r.resolve(root_deps);
-                r.download_all();
+    r.offline = options.offline;
+    r.checksum_policy = config.verification.as_ref()
+        .and_then(|v| v.checksums)
+        .unwrap_or_default();
+    r.conflict_strategy = config.resolution.as_ref()
+        .and_then(|r| r.strategy)
+        .unwrap_or_default();
 
-                let all_downloaded = r.verify_all_artifacts_exist();
+    apply_graph_rules(config, &mut r)?;
 
-                if all_downloaded {
-                    fs::create_dir_all(&project_cache_dir)?;
-                    fs::write(&lock_file, current_fingerprint)?;
-                    info!("Dependencies resolved and cached");
-                } else {
-                    warn!("Some dependencies failed to download will retry next run");
-                }
+    let mut settings = HttpSettings::default();
+
+    if let Some(network) = &config.network {
+        settings.retries = network.retries.unwrap_or(settings.retries);
+        settings.backoff = network.backoff.map(Duration::from_millis).unwrap_or(settings.backoff);
+        settings.timeout = network.timeout.map(Duration::from_secs).unwrap_or(settings.timeout);
+    }
+
+    if let Some(proxy) = &user.proxy {
+        settings.proxy = Some(proxy.url.clone());
+        settings.no_proxy = proxy.no_proxy.clone();
+    }
+
+    settings.ca_bundle = user.tls.and_then(|t| t.ca_bundle);
 
-                resolver = Some(r);
+    r.set_network(&settings)?;
+
+    let mirrors = user.mirrors.iter()
+        .map(|m| Ok(Mirror {
+            repository: Repository::named(&m.name, &m.url)?,
+            mirror_of: m.mirror_of.clone(),
+        }))
+        .collect::<Result<Vec<_>, String>>()?;
+
+    r.set_mirrors(mirrors);
+
+    Ok(r)
+}
+
+/// Правила для графа из spawn.toml: scope и exclude корневых зависимостей,
+/// [constraints] и [substitutions]. Локальные файлы из [substitutions]
+/// для resolver это просто удаление, в classpath их добавляет libraries
+fn apply_graph_rules(config: &parser::Config, r: &mut Resolver) -> Result<(), String> {
+    for (name, dep) in config.dependencies.iter().flatten() {
+        r.root_scopes.insert(name.clone(), dep.scope().scope());
+
+        if dep.exclude().is_empty() {
+            continue;
+        }
+
+        let exclusions = dep.exclude().iter()
+            .map(|e| Exclusion::parse(e).map_err(|e| format!("Dependency \"{}\": {}", name, e)))
+            .collect::<Result<Vec<_>, String>>()?;
+
+        r.exclusions.insert(name.clone(), exclusions);
+    }
+
+    for (id, version) in config.constraints.iter().flatten() {
+        check_id(id, "Constraint")?;
+
+        if VersionRange::is_range(version) {
+            let range = VersionRange::parse(version)
+                .map_err(|e| format!("Constraint \"{}\": invalid range {}: {}", id, version, e))?;
+
+            r.constraints.insert(id.clone(), range);
+        } else if version == "LATEST" || version == "RELEASE" {
+            return Err(format!("Constraint \"{}\": use a version or a range, not {}", id, version));
+        } else {
+            r.forced.insert(id.clone(), version.trim().to_string());
+        }
+    }
+
+    for (id, substitution) in config.substitutions.iter().flatten() {
+        check_id(id, "Substitution")?;
+
+        let replacement = match substitution {
+            Substitution::Remove(_) | Substitution::Local(_) => None,
+
+            Substitution::Artifact(coords) => match coords.split(':').collect::<Vec<_>>().as_slice() {
+                [group, name] => Some(Artifact::new(group, name, "")),
+                [group, name, version] => Some(Artifact::new(group, name, version)),
+                _ => return Err(format!(
+                    "Substitution \"{}\": expected group:name or group:name:version, got \"{}\"",
+                    id, coords
+                )),
+            },
+        };
+
+        r.substitutions.insert(id.clone(), replacement);
+    }
+
+    Ok(())
+}
+
+fn check_id(id: &str, what: &str) -> Result<(), String> {
+    match id.split(':').collect::<Vec<_>>().as_slice() {
+        [group, name] if !group.is_empty() && !name.is_empty() => Ok(()),
+        _ => Err(format!("{} \"{}\": expected group:name", what, id)),
+    }
+}
+
+/// Полный список репозиториев в порядке опроса: ~/.m2/repository (если
+/// включён maven_local), встроенные (если не отключены через
+/// default_repositories), потом из конфига. Запись с тем же именем или URL
+/// что у встроенного заменяет его на том же месте
+fn repositories(config: &parser::Config) -> Result<Vec<Repository>, String> {
+    let defaults: Vec<String> = match &config.default_repositories {
+        None | Some(DefaultRepositories::All(true)) => {
+            DEFAULT_REPOSITORIES.iter().map(|(name, _)| name.to_string()).collect()
+        }
+        Some(DefaultRepositories::All(false)) => Vec::new(),
+        Some(DefaultRepositories::Only(names)) => names.clone(),
+    };
+
+    let mut list = defaults.iter()
+        .map(|name| Repository::builtin(name).ok_or_else(|| unknown_builtin(name)))
+        .collect::<Result<Vec<_>, String>>()?;
+
+    // Локально собранные библиотеки важнее опубликованных
+    if config.maven_local == Some(true) {
+        let home = home_dir().ok_or("Cannot find home directory")?;
+        list.insert(0, Repository::local(MAVEN_LOCAL, &home.join(".m2").join("repository")));
+    }
+
+    for entry in config.repositories.iter().flatten() {
+        let repo = match entry {
+            RepositoryConfig::Url(url) => Repository::new(url),
+            RepositoryConfig::Named(named) => {
+                let url = match &named.url {
+                    Some(url) => url.clone(),
+                    None => list.iter()
+                        .find(|r| r.name.as_deref() == Some(named.name.as_str()))
+                        .cloned()
+                        .or_else(|| Repository::builtin(&named.name))
+                        .map(|r| r.url)
+                        .ok_or_else(|| format!("Repository '{}' has no url", named.name))?,
+                };
+
+                let mut repo = Repository::named(&named.name, &url)?;
+                repo.include = named.include.clone().unwrap_or_default();
+                repo.exclude = named.exclude.clone().unwrap_or_default();
+                repo
             }
+        };
+
+        let existing = list.iter_mut()
+            .find(|r| r.url == repo.url || (repo.name.is_some() && r.name == repo.name));
+
+        match existing {
+            Some(existing) => *existing = repo,
+            None => list.push(repo),
         }
+    }
+
+    Ok(list)
+}
+
+fn unknown_builtin(name: &str) -> String {
+    let names: Vec<&str> = DEFAULT_REPOSITORIES.iter().map(|(n, _)| *n).collect();
+    format!("Unknown default repository '{}', available: [{}]", name, names.join(", "))
+}
+
+/// Отчёт по всем артефактам которые не удалось разрешить или скачать
+fn failure_report(r: &Resolver, options: &BuildOptions) -> String {
+    let report = error::report(&r.unresolved);
+
+    if options.offline {
+        format!("Offline mode, local repository {}\n{}", r.cache_root.display(), report)
     } else {
-        info!("No dependencies section — building without external libs");
+        report
     }
+}
 
-    Ok((config, resolver))
+/// Координаты корневых зависимостей проекта в виде group:name:version,
+/// с :classifier и @ext если они заданы. Path зависимости сюда не входят
+fn root_coords(config: &parser::Config) -> Vec<String> {
+    config.dependencies.iter()
+        .flatten()
+        .filter_map(|(k, v)| Some(format!("{}:{}{}", k, v.version()?, v.coords_suffix())))
+        .collect()
 }
 
 fn generate_fingerprint(config: &parser::Config) -> String {
@@ -84,18 +410,76 @@ fn generate_fingerprint(config: &parser::Config) -> String {
         let mut sorted: Vec<_> = deps.iter().collect();
         sorted.sort_by_key(|a| a.0);
 
+        // Path зависимости не влияют на граф и в lock не попадают
         for (k, v) in sorted {
+            let Some(version) = v.version() else {
+                continue;
+            };
+
             hasher.update(k.as_bytes());
             hasher.update(b":");
-            hasher.update(v.as_bytes());
+            hasher.update(version.as_bytes());
+            hasher.update(v.coords_suffix().as_bytes());
+
+            for exclusion in v.exclude() {
+                hasher.update(exclusion.as_bytes());
+            }
+
+            if v.scope() != DependencyScope::default() {
+                hasher.update(format!("scope:{:?}", v.scope()).as_bytes());
+            }
         }
     }
 
     if let Some(repos) = &config.repositories {
         for r in repos {
-            hasher.update(r.as_bytes());
+            match r {
+                RepositoryConfig::Url(url) => hasher.update(url.as_bytes()),
+                RepositoryConfig::Named(n) => {
+                    hasher.update(n.name.as_bytes());
+                    hasher.update(n.url.as_deref().unwrap_or_default().as_bytes());
+
+                    for pattern in n.include.iter().flatten().chain(n.exclude.iter().flatten()) {
+                        hasher.update(pattern.as_bytes());
+                    }
+                }
+            }
+        }
+    }
+
+    if let Some(defaults) = &config.default_repositories {
+        hasher.update(format!("{:?}", defaults).as_bytes());
+    }
+
+    if config.maven_local == Some(true) {
+        hasher.update(MAVEN_LOCAL.as_bytes());
+    }
+
+    if let Some(constraints) = &config.constraints {
+        let mut sorted: Vec<_> = constraints.iter().collect();
+        sorted.sort();
+
+        for (id, version) in sorted {
+            hasher.update(format!("constraint:{}={}", id, version).as_bytes());
         }
     }
 
+    if let Some(substitutions) = &config.substitutions {
+        let mut sorted: Vec<_> = substitutions.iter().collect();
+        sorted.sort_by_key(|a| a.0);
+
+        for (id, substitution) in sorted {
+            hasher.update(format!("substitution:{}={:?}", id, substitution).as_bytes());
+        }
+    }
+
+    // Другая стратегия даёт другой граф. По умолчанию не пишется, чтобы
+    // старые lock файлы оставались актуальными
+    let strategy = config.resolution.as_ref().and_then(|r| r.strategy).unwrap_or_default();
+
+    if strategy != ConflictStrategy::default() {
+        hasher.update(format!("strategy:{}", strategy).as_bytes());
+    }
+
     format!("{:x}", hasher.finalize())
 }
\ No newline at end of file
@@ -5,10 +5,13 @@
 // SPDX-License-Identifier: EPL-2.0
 
 use std::fs;
+use std::path::{Path, PathBuf};
 use walkdir::WalkDir;
 
+use crate::frontend::{libraries, BuildOptions};
 use crate::parser::Config;
 use crate::resolver::Resolver;
+use crate::resolver::graph::Scope;
 
 pub fn generate_ninja(
     config: &Config,
@@ -16,6 +19,7 @@ pub fn generate_ninja(
     
     // Формат выходного файла, тут либо apk либо aab
     output_type: &str, 
+    options: &BuildOptions,
 ) -> Result<(), Box<dyn std::error::Error>> {
     task!("Generate build.ninja");
 
@@ -29,7 +33,7 @@ pub fn generate_ninja(
 
     ninja.push_str(&format!("builddir = {}\n", build_dir.display()));
     ninja.push_str(&format!("cachedir = {}\n", cache_dir.display()));
-    ninja.push_str("\n");
+    ninja.push('\n');
 
     let target_sdk = config.package.as_ref()
         .and_then(|p| p.target_sdk)
@@ -43,69 +47,126 @@ pub fn generate_ninja(
     ninja.push_str("zip = zip\n");
     ninja.push_str("zipalign = zipalign\n");
     ninja.push_str("apksigner = apksigner\n");
-    ninja.push_str("bundletool = java -jar $BUNDLETOOL_JAR\n\n");
+    ninja.push_str("bundletool = java -jar $BUNDLETOOL_JAR\n");
+    ninja.push_str(&format!("d8_mode = {}\n\n", if options.debug { "--debug" } else { "--release" }));
 
-    ninja.push_str("rule javac\n");
-    ninja.push_str("  command = $javac -d $outdir -classpath $classpath -source 1.8 -target 1.8 $in\n");
-    ninja.push_str("  description = JAVAC $in\n\n");
+    // spawn сам ninja не запускает, поэтому -j ограничивает сборку через пул
+    if let Some(jobs) = options.jobs {
+        ninja.push_str(&format!("pool jobs\n  depth = {}\n\n", jobs));
+    }
 
-    ninja.push_str("rule kotlinc\n");
-    ninja.push_str("  command = $kotlinc -d $outdir -classpath $classpath -jvm-target 1.8 $in\n");
-    ninja.push_str("  description = KOTLINC $in\n\n");
+    let rules = [
+        ("javac", "$javac -d $outdir -classpath $classpath $javac_flags -source 1.8 -target 1.8 $in", "JAVAC $in"),
+        ("kotlinc", "$kotlinc -d $outdir -classpath $classpath -jvm-target 1.8 $in", "KOTLINC $in"),
+        ("aapt2_compile", "$aapt2 compile --dir $in -o $out", "AAPT2 compile $in"),
+        ("aapt2_link", "$aapt2 link -o $out --manifest $manifest -I $android_jar --auto-add-overlay $in", "AAPT2 link"),
+        ("d8", "$d8 $d8_mode --output $out $in", "D8/R8 optimization"),
+        ("package_apk", "cd $builddir && $zip -r $out . && cd -", "Packaging unsigned APK"),
+        ("zipalign", "$zipalign -f -v 4 $in $out", "Aligning APK"),
+        ("apksigner", "$apksigner sign --ks $keystore --ks-key-alias $alias --out $out $in", "Signing APK"),
+        ("build_aab", "$bundletool build-bundle --modules $modules_dir --output $out", "Building AAB"),
+    ];
+
+    for (name, command, description) in rules {
+        ninja.push_str(&format!("rule {}\n", name));
+        ninja.push_str(&format!("  command = {}\n", command));
+
+        if options.jobs.is_some() {
+            ninja.push_str("  pool = jobs\n");
+        }
 
-    ninja.push_str("rule aapt2_compile\n");
-    ninja.push_str("  command = $aapt2 compile --dir $in -o $out\n");
-    ninja.push_str("  description = AAPT2 compile $in\n\n");
+        ninja.push_str(&format!("  description = {}\n\n", description));
+    }
 
-    ninja.push_str("rule aapt2_link\n");
-    ninja.push_str("  command = $aapt2 link -o $out --manifest $manifest -I $android_jar --auto-add-overlay $in\n");
-    ninja.push_str("  description = AAPT2 link\n\n");
+    // В classpath javac идут compile и provided зависимости, в APK compile
+    // и runtime, test только в classpath тестов
+    let mut classpath = String::from("$android_jar");
+    let mut test_jars = Vec::new();
+    let mut processor_jars = Vec::new();
+    let mut packaged_jars = Vec::new();
 
-    ninja.push_str("rule d8\n");
-    ninja.push_str("  command = $d8 --release --output $out $in\n");
-    ninja.push_str("  description = D8/R8 optimization\n\n");
+    let mut jars = Vec::new();
 
-    ninja.push_str("rule package_apk\n");
-    ninja.push_str("  command = cd $builddir && $zip -r $out . && cd -\n");
-    ninja.push_str("  description = Packaging unsigned APK\n\n");
+    if let Some(r) = resolver {
+        let mut artifacts: Vec<_> = r.resolved_artifacts.values().collect();
+        artifacts.sort_by_key(|a| a.id());
+
+        for art in artifacts {
+            // Aar распаковывается в unpacked/ рядом с ним, jar идёт как есть,
+            // остальные файлы (zip, so) в classpath не нужны
+            let jar = match r.artifact_file(art) {
+                Some((path, ext)) if ext == "aar" => path.parent().unwrap().join("unpacked").join("classes.jar"),
+                Some((path, ext)) if ext == "jar" => path,
+                _ => continue,
+            };
+
+            if r.processors.contains(&art.id()) {
+                processor_jars.push(jar.clone());
+            }
 
-    ninja.push_str("rule zipalign\n");
-    ninja.push_str("  command = $zipalign -f -v 4 $in $out\n");
-    ninja.push_str("  description = Aligning APK\n\n");
+            jars.push((jar, r.scope(art)));
+        }
+    }
 
-    ninja.push_str("rule apksigner\n");
-    ninja.push_str("  command = $apksigner sign --ks $keystore --ks-key-alias $alias --out $out $in\n");
-    ninja.push_str("  description = Signing APK\n\n");
+    jars.extend(libraries::jars(config)?);
 
-    ninja.push_str("rule build_aab\n");
-    ninja.push_str("  command = $bundletool build-bundle --modules $modules_dir --output $out\n");
-    ninja.push_str("  description = Building AAB\n\n");
+    for (jar, scope) in jars {
+        if !jar.exists() {
+            continue;
+        }
 
-    let mut classpath = String::from("$android_jar");
-    if let Some(r) = resolver {
-        for (_, art) in &r.resolved_artifacts {
-            let unpacked = r.cache_root.join(art.get_path("unpacked"));
-            let classes_jar = unpacked.join("classes.jar");
+        if scope.on_classpath() {
+            classpath.push_str(&format!(":{}", jar.display()));
+        }
 
-            if classes_jar.exists() {
-                classpath.push_str(&format!(":{}", classes_jar.display()));
-            }
+        if scope.packaged() {
+            packaged_jars.push(jar.clone());
+        }
+
+        match scope {
+            Scope::Test => test_jars.push(jar),
+            Scope::Processor if !processor_jars.contains(&jar) => processor_jars.push(jar),
+            _ => {}
         }
     }
-    ninja.push_str(&format!("classpath = {}\n\n", classpath));
+
+    ninja.push_str(&format!("classpath = {}\n", classpath));
+
+    // Для test/java модулей, в приложение не попадает
+    if test_jars.is_empty() {
+        ninja.push_str("test_classpath = $classpath\n");
+    } else {
+        ninja.push_str(&format!("test_classpath = $classpath:{}\n", join_paths(&test_jars)));
+    }
+
+    // Без -processorpath javac ищет процессоры в classpath
+    if !processor_jars.is_empty() {
+        ninja.push_str(&format!("javac_flags = -processorpath {}\n", join_paths(&processor_jars)));
+    }
+
+    ninja.push('\n');
 
     let mut module_dirs = Vec::new();
     for module_path in &config.modules {
-        let dir = module_path.parent().unwrap().to_path_buf();
+        let dir = module_path.parent()
+            .filter(|p| !p.as_os_str().is_empty())
+            .unwrap_or(Path::new("."))
+            .to_path_buf();
         module_dirs.push(dir);
     }
 
     let mut all_classes_dirs = Vec::new();
+    let mut all_test_classes_dirs = Vec::new();
     let mut all_flat_resources = Vec::new();
 
     // Компиляция каждого модуля, все модули компилируются и потом линкуются
     for module_dir in &module_dirs {
-        let module_name = module_dir.file_name().unwrap().to_string_lossy();
+        // У "." нет file_name, поэтому для корня проекта берётся имя настоящей папки
+        let module_name = fs::canonicalize(module_dir)
+            .unwrap_or_else(|_| module_dir.clone())
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "root".to_string());
 
         let java_dir = module_dir.join("java");
         let kotlin_dir = module_dir.join("kotlin");
@@ -121,7 +182,7 @@ pub fn generate_ninja(
         let mut java_sources = String::new();
         if java_dir.exists() {
             for entry in WalkDir::new(&java_dir).into_iter().filter_map(|e| e.ok()) {
-                if entry.path().extension().map_or(false, |e| e == "java") {
+                if entry.path().extension().is_some_and(|e| e == "java") {
                     java_sources.push_str(&format!("{} ", entry.path().display()));
                 }
             }
@@ -142,11 +203,19 @@ pub fn generate_ninja(
         let mut kotlin_sources = String::new();
         if kotlin_dir.exists() {
             for entry in WalkDir::new(&kotlin_dir).into_iter().filter_map(|e| e.ok()) {
-                if entry.path().extension().map_or(false, |e| e == "kt") {
+                if entry.path().extension().is_some_and(|e| e == "kt") {
                     kotlin_sources.push_str(&format!("{} ", entry.path().display()));
                 }
             }
 
+            // kotlinc процессоры не запускает, а kapt spawn не умеет
+            if !kotlin_sources.is_empty() && !processor_jars.is_empty() {
+                return Err(format!(
+                    "Module {} has Kotlin sources, annotation processors are supported only for Java",
+                    module_name
+                ).into());
+            }
+
             if !kotlin_sources.is_empty() {
                 ninja.push_str(&format!(
                     "build {}: kotlinc {}\n",
@@ -159,6 +228,35 @@ pub fn generate_ninja(
             }
         }
 
+        // Тесты компилируются против классов модуля, но в APK не идут.
+        // Собираются отдельной целью tests
+        let test_java_dir = module_dir.join("test").join("java");
+        let mut test_sources = String::new();
+        if test_java_dir.exists() {
+            for entry in WalkDir::new(&test_java_dir).into_iter().filter_map(|e| e.ok()) {
+                if entry.path().extension().is_some_and(|e| e == "java") {
+                    test_sources.push_str(&format!("{} ", entry.path().display()));
+                }
+            }
+        }
+
+        if !test_sources.is_empty() {
+            let test_classes_dir = module_out_dir.join("test-classes");
+            let has_classes = !java_sources.is_empty() || !kotlin_sources.is_empty();
+
+            ninja.push_str(&format!(
+                "build {}: javac {}{}\n",
+                test_classes_dir.display(),
+                test_sources.trim(),
+                if has_classes { format!(" | {}", module_classes_dir.display()) } else { String::new() }
+            ));
+
+            ninja.push_str(&format!("  classpath = $test_classpath:{}\n", module_classes_dir.display()));
+            ninja.push_str(&format!("  outdir = {}\n\n", test_classes_dir.display()));
+
+            all_test_classes_dirs.push(test_classes_dir);
+        }
+
         // И для ресурсов компиляция
         if res_dir.exists() {
             ninja.push_str(&format!(
@@ -171,6 +269,15 @@ pub fn generate_ninja(
         }
     }
 
+    if !all_test_classes_dirs.is_empty() {
+        let inputs = all_test_classes_dirs.iter()
+            .map(|p| p.display().to_string())
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        ninja.push_str(&format!("build tests: phony {}\n\n", inputs));
+    }
+
     // Линковка тут обзая для всех модулей
     let linked_resources = build_dir.join("linked_resources.ap_");
     if !all_flat_resources.is_empty() {
@@ -186,12 +293,13 @@ pub fn generate_ninja(
         ));
 
         ninja.push_str(&format!("  manifest = {}\n", cache_dir.join("AndroidManifest.xml").display()));
-        ninja.push_str("\n");
+        ninja.push('\n');
     }
 
     // Дексинг
     let dex_dir = build_dir.join("dex");
     let dex_inputs = all_classes_dirs.iter()
+        .chain(&packaged_jars)
         .map(|p| p.display().to_string())
         .collect::<Vec<_>>()
         .join(" ");
@@ -203,7 +311,7 @@ pub fn generate_ninja(
             dex_inputs
         ));
 
-        ninja.push_str("\n");
+        ninja.push('\n');
     }
 
     // Финальная сборка
@@ -222,7 +330,7 @@ pub fn generate_ninja(
             ninja.push_str(&format!("  resources = {}\n", linked_resources.display()));
         }
 
-        ninja.push_str("\n");
+        ninja.push('\n');
 
         ninja.push_str(&format!(
             "build {}: zipalign {}\n",
@@ -230,7 +338,7 @@ pub fn generate_ninja(
             unsigned_apk.display()
         ));
 
-        ninja.push_str("\n");
+        ninja.push('\n');
 
         ninja.push_str(&format!(
             "build {}: apksigner {}\n",
@@ -243,7 +351,7 @@ pub fn generate_ninja(
             ninja.push_str(&format!("  alias = {}\n", sign.alias));
         }
 
-        ninja.push_str("\n");
+        ninja.push('\n');
 
         ninja.push_str(&format!("default {}\n", signed_apk.display()));
     } else {
@@ -265,4 +373,11 @@ pub fn generate_ninja(
     fs::write(&ninja_path, ninja)?;
     
     Ok(())
-}
\ No newline at end of file
+}
+
+fn join_paths(paths: &[PathBuf]) -> String {
+    paths.iter()
+        .map(|p| p.display().to_string())
+        .collect::<Vec<_>>()
+        .join(":")
+}
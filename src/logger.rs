@@ -4,13 +4,48 @@
 // https://www.eclipse.org/legal/epl-2.0/
 // SPDX-License-Identifier: EPL-2.0
 
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+static VERBOSE: AtomicBool = AtomicBool::new(false);
+
+/// Пароли и токены репозиториев, которые не должны попасть в вывод
+static SECRETS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+/// Включает вывод сообщений debug! (флаг --verbose)
+pub fn set_verbose(enabled: bool) {
+    VERBOSE.store(enabled, Ordering::Relaxed);
+}
+
+pub fn is_verbose() -> bool {
+    VERBOSE.load(Ordering::Relaxed)
+}
+
+/// Запоминает секрет, дальше все макросы вывода заменяют его на ***
+pub fn add_secret(secret: &str) {
+    if !secret.is_empty() {
+        SECRETS.lock().unwrap().push(secret.to_string());
+    }
+}
+
+/// Убирает из сообщения все известные секреты
+pub fn scrub(message: String) -> String {
+    let secrets = SECRETS.lock().unwrap();
+
+    if secrets.is_empty() {
+        return message;
+    }
+
+    secrets.iter().fold(message, |m, s| m.replace(s.as_str(), "***"))
+}
+
 /// Макрос для вывода информации о текущей задаче/этапе сборки
 /// Выводит "TASK: Сообщение" где TASK зелёным и жирным
 #[macro_export]
 macro_rules! task {
     ($($arg:tt)*) => {{
         use colored::Colorize;
-        println!("{} {}", "TASK:".green().bold(), format!($($arg)*));
+        println!("{} {}", "TASK:".green().bold(), $crate::logger::scrub(format!($($arg)*)));
     }};
 }
 
@@ -18,7 +53,19 @@ macro_rules! task {
 #[macro_export]
 macro_rules! info {
     ($($arg:tt)*) => {{
-        println!("{}", format!($($arg)*));
+        println!("{}", $crate::logger::scrub(format!($($arg)*)));
+    }};
+}
+
+/// Макрос для подробного вывода, печатается только с флагом --verbose.
+/// Префикс "DEBUG:", цвет серый
+#[macro_export]
+macro_rules! debug {
+    ($($arg:tt)*) => {{
+        if $crate::logger::is_verbose() {
+            use colored::Colorize;
+            println!("{} {}", "DEBUG:".bright_black(), $crate::logger::scrub(format!($($arg)*)));
+        }
     }};
 }
 
@@ -27,7 +74,7 @@ macro_rules! info {
 macro_rules! note {
     ($($arg:tt)*) => {{
         use colored::Colorize;
-        println!("{} {}", "NOTE:".blue().bold(), format!($($arg)*));
+        println!("{} {}", "NOTE:".blue().bold(), $crate::logger::scrub(format!($($arg)*)));
     }};
 }
 
@@ -36,7 +83,7 @@ macro_rules! note {
 macro_rules! hook {
     ($($arg:tt)*) => {{
         use colored::Colorize;
-        println!("{} {}", "HOOK:".purple().bold(), format!($($arg)*));
+        println!("{} {}", "HOOK:".purple().bold(), $crate::logger::scrub(format!($($arg)*)));
     }};
 }
 
@@ -46,7 +93,7 @@ macro_rules! hook {
 macro_rules! warn {
     ($($arg:tt)*) => {{
         use colored::Colorize;
-        eprintln!("{} {}", "WARN:".yellow().bold(), format!($($arg)*));
+        eprintln!("{} {}", "WARN:".yellow().bold(), $crate::logger::scrub(format!($($arg)*)));
     }};
 }
 
@@ -56,7 +103,7 @@ macro_rules! warn {
 macro_rules! error {
     ($($arg:tt)*) => {{
         use colored::Colorize;
-        eprintln!("{} {}", "ERROR:".red().bold(), format!($($arg)*));
+        eprintln!("{} {}", "ERROR:".red().bold(), $crate::logger::scrub(format!($($arg)*)));
     }};
 }
 
@@ -67,7 +114,7 @@ macro_rules! error {
 macro_rules! fatal {
     ($($arg:tt)*) => {{
         use colored::Colorize;
-        eprintln!("{} {}", "FATAL:".red().bold().on_black(), format!($($arg)*));
+        eprintln!("{} {}", "FATAL:".red().bold().on_black(), $crate::logger::scrub(format!($($arg)*)));
         std::process::exit(1);
     }};
 }
\ No newline at end of file
@@ -8,47 +8,187 @@
 mod logger;
 
 mod build_system;
+mod completions;
 mod parser;
 mod frontend;
 mod resolver;
 
-use build_system::{BuildSystem, Actions};
+use std::path::PathBuf;
+
+use build_system::{BuildSystem, Actions, Cli};
+use frontend::{BuildOptions, LockMode};
+use frontend::init::InitOptions;
 
 fn main() {
-    let mut build_system = BuildSystem::new();
-    let action = build_system.get_action();
+    let build_system = BuildSystem::new();
+
+    let cli = match build_system.parse() {
+        Ok(cli) => cli,
+        Err(e) => {
+            error!("{}", e);
+            eprintln!("Run 'spawn help' for usage");
+            std::process::exit(2);
+        }
+    };
+
+    if cli.flag("version") {
+        build_system.print_version();
+        return;
+    }
+
+    if let Some(command) = cli.command
+        && cli.flag("help")
+    {
+        build_system.print_command_help(command);
+        return;
+    }
 
-    match action {
+    logger::set_verbose(cli.flag("verbose"));
+
+    match cli.action {
         Actions::BuildApk => {
-            let paths = get_paths(build_system.args.clone());
-            if paths.is_empty() {
-                build_system.print_help();
-                panic!("No toml file provided");
-            }
+            let options = get_build_options(&cli);
 
-            let (config, resolver) = match frontend::prepare(paths) {
+            let (config, resolver) = match frontend::prepare(cli.paths(), &options) {
                 Ok(result) => result,
                 Err(e) => fatal!("Prepare failed: {}", e),
             };
 
-            frontend::ninja_generator::generate_ninja(&config, resolver.as_ref(), "apk")
-                .expect("Generate ninja failed");
+            if let Err(e) = frontend::ninja_generator::generate_ninja(&config, resolver.as_ref(), "apk", &options) {
+                fatal!("Generate ninja failed: {}", e);
+            }
 
             println!("Build finish");
         },
 
+        Actions::Check => {
+            let options = get_build_options(&cli);
+
+            let report = frontend::check::check(cli.paths(), &options, cli.flag("resolve"));
+            report.print();
+
+            if !report.is_ok() {
+                fatal!("Check failed: {} error(s)", report.errors.len());
+            }
+
+            println!("Check passed");
+        },
+
+        Actions::Lock => {
+            let options = get_build_options(&cli);
+
+            // Аргументы вида group:name это артефакты для обновления, остальное пути
+            let (ids, paths): (Vec<String>, Vec<String>) = cli.args.iter()
+                .cloned()
+                .partition(|a| build_system::is_module_id(a));
+
+            let mode = match (cli.flag("update"), ids.is_empty()) {
+                (false, true) => LockMode::Use,
+                (false, false) => fatal!("Artifacts can be listed only with --update"),
+                (true, true) => LockMode::UpdateAll,
+                (true, false) => LockMode::Update(ids),
+            };
+
+            let paths = if paths.is_empty() { vec!["spawn.toml".to_string()] } else { paths };
+
+            if let Err(e) = frontend::lock(paths, &options, &mode) {
+                fatal!("Lock failed: {}", e);
+            }
+        },
+
+        Actions::Init => {
+            let options = get_init_options(&cli);
+
+            match frontend::init::init(&options) {
+                Ok(files) => {
+                    for f in files {
+                        info!(" Created {}", f.display());
+                    }
+                },
+                Err(e) => fatal!("Init failed: {}", e),
+            }
+
+            println!("Project created");
+        },
+
+        Actions::Completions => {
+            let Some(shell) = cli.args.first() else {
+                fatal!("Shell name required: spawn completions bash|zsh|fish");
+            };
+
+            // Вызывается самими скриптами дополнения при вводе --variant
+            if shell == "variants" {
+                for name in completions::variants() {
+                    println!("{}", name);
+                }
+
+                return;
+            }
+
+            match completions::generate(shell) {
+                Ok(script) => print!("{}", script),
+                Err(e) => fatal!("{}", e),
+            }
+        },
+
         Actions::Help => {
-            build_system.print_help();
+            // spawn help apk показывает справку по конкретной команде
+            match cli.args.first() {
+                Some(name) => match build_system::find_command(name) {
+                    Some(command) => build_system.print_command_help(command),
+                    None => fatal!("Unknown command '{}'", name),
+                },
+                None => build_system.print_help(),
+            }
         },
 
-        _ => todo!(),
+        Actions::BuildAab | Actions::Up | Actions::Clean => {
+            fatal!("'{}' is not implemented yet", cli.command.map_or("", |c| c.name));
+        },
     }
 }
 
-fn get_paths(args: Vec<String>) -> Vec<String> {
-    if args.len() >= 3 {
-        return args[2..].to_vec();
+fn get_build_options(cli: &Cli) -> BuildOptions {
+    let jobs = cli.value("jobs").map(|jobs| match jobs.parse() {
+        Ok(n) if n > 0 => n,
+        _ => fatal!("Invalid value for --jobs: {}", jobs),
+    });
+
+    // Пул rayon используется для параллельного скачивания зависимостей,
+    // сборку ограничивает пул jobs в build.ninja
+    if let Some(jobs) = jobs {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(jobs)
+            .build_global()
+            .ok();
     }
 
-    vec![]
+    if cli.flag("release") && cli.flag("debug") {
+        fatal!("--release and --debug can't be used together");
+    }
+
+    BuildOptions {
+        debug: cli.flag("debug"),
+        offline: cli.flag("offline"),
+        variant: cli.value("variant").map(|v| v.to_string()),
+        jobs,
+    }
+}
+
+fn get_init_options(cli: &Cli) -> InitOptions {
+    if cli.args.len() > 1 {
+        fatal!("init accepts only one directory, got: {}", cli.args.join(" "));
+    }
+
+    InitOptions {
+        dir: cli.args.first().map(PathBuf::from).unwrap_or_else(|| PathBuf::from(".")),
+        package: cli.value("package").unwrap_or("com.example.app").to_string(),
+        kotlin: cli.flag("kotlin"),
+        modules: cli.value("modules")
+            .map(|m| m.split(',')
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .collect())
+            .unwrap_or_default(),
+    }
 }
\ No newline at end of file
@@ -6,6 +6,7 @@
 
 pub mod toml_parser;
 pub mod manifest_generator;
+pub mod user_config;
 
 pub use toml_parser::Config;
 use std::error::Error;
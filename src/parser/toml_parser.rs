@@ -6,16 +6,47 @@
 
 use serde::Deserialize;
 use walkdir::WalkDir;
+use indexmap::IndexMap;
 use std::collections::HashMap;
 use std::fs;
 use std::path::{Path, PathBuf};
 
+use crate::resolver::checksum::ChecksumPolicy;
+use crate::resolver::graph::{ConflictStrategy, Scope};
+use crate::resolver::signature::SignaturePolicy;
+
 #[derive(Debug, Deserialize, Default)]
 pub struct Config {
     pub package: Option<PackageInfo>,
     pub sign: Option<SignInfo>,
-    pub repositories: Option<Vec<String>>,
-    pub dependencies: Option<HashMap<String, String>>,
+    pub repositories: Option<Vec<RepositoryConfig>>,
+
+    /// Встроенные репозитории: true (по умолчанию), false или список имён
+    pub default_repositories: Option<DefaultRepositories>,
+
+    /// Локальный репозиторий Maven ~/.m2/repository, опрашивается первым
+    #[serde(alias = "mavenLocal")]
+    pub maven_local: Option<bool>,
+
+    /// В порядке объявления: при равной глубине стратегия nearest
+    /// выбирает объявленную раньше
+    pub dependencies: Option<IndexMap<String, Dependency>>,
+
+    /// Версии для всего графа: "group:name" = "1.0" заменяет любую
+    /// запрошенную версию, "group:name" = "[1.0,2.0)" ограничивает выбор
+    pub constraints: Option<HashMap<String, String>>,
+
+    /// Чем заменить артефакт где бы он ни встретился в графе
+    pub substitutions: Option<HashMap<String, Substitution>>,
+
+    /// Варианты сборки [variants.<name>], переопределяют поля из [package]
+    pub variants: Option<HashMap<String, PackageInfo>>,
+
+    pub verification: Option<VerificationConfig>,
+
+    pub resolution: Option<ResolutionConfig>,
+
+    pub network: Option<NetworkConfig>,
     
     #[serde(skip)]
     pub base_path: PathBuf,
@@ -24,7 +55,7 @@ pub struct Config {
     pub modules: Vec<PathBuf>,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Clone, Default)]
 pub struct PackageInfo {
     pub package: Option<String>,
     pub version: Option<String>,
@@ -41,11 +72,348 @@ pub struct SignInfo {
     pub alias: String,
 }
 
+impl Config {
+    /// Имена вариантов из [variants.<name>] по алфавиту
+    pub fn variant_names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.variants.iter().flatten().map(|(k, _)| k.as_str()).collect();
+        names.sort();
+        names
+    }
+
+    /// Применяет вариант сборки: поля заданные в [variants.<name>] заменяют
+    /// соответствующие поля [package], остальные остаются как есть
+    pub fn apply_variant(&mut self, name: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let Some(variant) = self.variants.as_ref().and_then(|v| v.get(name)).cloned() else {
+            return Err(format!("Unknown variant '{}', available: [{}]", name, self.variant_names().join(", ")).into());
+        };
+
+        let package = self.package.get_or_insert_with(PackageInfo::default);
+
+        package.package = variant.package.or(package.package.take());
+        package.version = variant.version.or(package.version.take());
+        package.version_code = variant.version_code.or(package.version_code);
+        package.label = variant.label.or(package.label.take());
+        package.icon = variant.icon.or(package.icon.take());
+        package.min_sdk = variant.min_sdk.or(package.min_sdk);
+        package.target_sdk = variant.target_sdk.or(package.target_sdk);
+
+        Ok(())
+    }
+}
+
+/// Секция [verification]: проверка целостности скачанных зависимостей
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct VerificationConfig {
+    /// strict (по умолчанию) или lenient для репозиториев без контрольных сумм
+    pub checksums: Option<ChecksumPolicy>,
+
+    /// off (по умолчанию), warn или strict для PGP подписей (.asc)
+    pub signatures: Option<SignaturePolicy>,
+
+    /// Файл с доверенными публичными ключами относительно проекта,
+    /// по умолчанию trusted-keys.asc
+    pub keys: Option<String>,
+
+    /// Доверенные отпечатки ключей по группам: "androidx" = ["..."],
+    /// "com.google.*" действует и на вложенные группы, "*" на все остальные
+    pub trusted: Option<HashMap<String, Vec<String>>>,
+}
+
+/// Секция [resolution]: как строится граф зависимостей
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct ResolutionConfig {
+    /// highest (по умолчанию), nearest или fail, если одна библиотека
+    /// запрошена в разных версиях
+    pub strategy: Option<ConflictStrategy>,
+}
+
+/// Репозиторий в конфиге: просто URL или таблица [[repositories]] с именем.
+/// По имени ищутся учётные данные (переменные окружения или
+/// ~/.spawn/credentials.toml), в файле проекта их быть не должно
+#[derive(Debug, Deserialize, Clone)]
+#[serde(untagged)]
+pub enum RepositoryConfig {
+    Url(String),
+    Named(NamedRepository),
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct NamedRepository {
+    pub name: String,
+
+    /// Можно не указывать для встроенных репозиториев (google, central,
+    /// huawei) и для mavenLocal
+    pub url: Option<String>,
+
+    /// Группы которые искать только в этом репозитории: ["androidx.*"]
+    pub include: Option<Vec<String>>,
+
+    /// Группы которые никогда не запрашивать из этого репозитория
+    pub exclude: Option<Vec<String>>,
+
+    // Эти поля читаются только чтобы выдать понятную ошибку
+    #[serde(default)]
+    username: Option<toml::Value>,
+
+    #[serde(default)]
+    password: Option<toml::Value>,
+
+    #[serde(default)]
+    token: Option<toml::Value>,
+}
+
+/// default_repositories = false или ["google", "central"]
+#[derive(Debug, Deserialize, Clone)]
+#[serde(untagged)]
+pub enum DefaultRepositories {
+    All(bool),
+    Only(Vec<String>),
+}
+
+impl RepositoryConfig {
+    pub fn url(&self) -> Option<&str> {
+        match self {
+            RepositoryConfig::Url(url) => Some(url),
+            RepositoryConfig::Named(repo) => repo.url.as_deref(),
+        }
+    }
+
+    /// Учётные данные в файле проекта (поля или user:pass@ в URL) попадут
+    /// в систему контроля версий, поэтому это ошибка
+    fn check_no_credentials(&self, path: &Path) -> Result<(), String> {
+        let in_url = self.url()
+            .and_then(|url| url.split_once("://"))
+            .is_some_and(|(_, rest)| rest.split('/').next().unwrap_or_default().contains('@'));
+
+        let in_fields = match self {
+            RepositoryConfig::Named(r) => r.username.is_some() || r.password.is_some() || r.token.is_some(),
+            RepositoryConfig::Url(_) => false,
+        };
+
+        if !in_url && !in_fields {
+            return Ok(());
+        }
+
+        let hint = match self {
+            RepositoryConfig::Named(r) => format!(
+                "set SPAWN_{0}_TOKEN or SPAWN_{0}_USERNAME/SPAWN_{0}_PASSWORD, or add [{1}] to ~/.spawn/credentials.toml",
+                r.name.to_ascii_uppercase().replace(|c: char| !c.is_ascii_alphanumeric(), "_"),
+                r.name
+            ),
+            RepositoryConfig::Url(_) => "use [[repositories]] with a name and keep credentials in ~/.spawn/credentials.toml".to_string(),
+        };
+
+        Err(format!("{}: repository credentials must not be stored in the project file, {}", path.display(), hint))
+    }
+}
+
+/// Зависимость в [dependencies]: версия "1.6.1" для group:name либо
+/// таблица. { path = "libs/foo.aar" } это файл или папка с .jar/.aar
+/// внутри проекта, они не проходят через разрешение pom
+#[derive(Debug, Deserialize, Clone)]
+#[serde(untagged)]
+pub enum Dependency {
+    Version(String),
+    Detailed(DependencyDetails),
+}
+
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(deny_unknown_fields)]
+pub struct DependencyDetails {
+    pub version: Option<String>,
+
+    /// Путь относительно файла где объявлена зависимость. После загрузки
+    /// конфига он уже приведён к пути от текущей папки
+    pub path: Option<String>,
+
+    /// Транзитивные зависимости которые не нужны: "group:name" или "group:*"
+    pub exclude: Option<Vec<String>>,
+
+    /// Куда идёт зависимость, по умолчанию implementation
+    pub scope: Option<DependencyScope>,
+
+    /// Classifier файла артефакта (natives-android, sources)
+    pub classifier: Option<String>,
+
+    /// Расширение файла, по умолчанию берётся из <packaging> pom
+    pub ext: Option<String>,
+}
+
+/// Scope зависимости в spawn.toml, названия как в Gradle
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum DependencyScope {
+    /// В classpath и в APK
+    #[default]
+    Implementation,
+
+    /// Только в classpath, например аннотации
+    CompileOnly,
+
+    /// Только в APK
+    RuntimeOnly,
+
+    /// Только в classpath тестов
+    TestImplementation,
+
+    /// В -processorpath javac вместе со своими зависимостями
+    AnnotationProcessor,
+}
+
+impl DependencyScope {
+    pub fn scope(self) -> Scope {
+        match self {
+            DependencyScope::Implementation => Scope::Compile,
+            DependencyScope::CompileOnly => Scope::Provided,
+            DependencyScope::RuntimeOnly => Scope::Runtime,
+            DependencyScope::TestImplementation => Scope::Test,
+            DependencyScope::AnnotationProcessor => Scope::Processor,
+        }
+    }
+}
+
+impl Dependency {
+    /// Версия Maven зависимости, None для path зависимостей
+    pub fn version(&self) -> Option<&str> {
+        match self {
+            Dependency::Version(v) => Some(v),
+            Dependency::Detailed(d) => d.version.as_deref(),
+        }
+    }
+
+    pub fn path(&self) -> Option<&str> {
+        match self {
+            Dependency::Version(_) => None,
+            Dependency::Detailed(d) => d.path.as_deref(),
+        }
+    }
+
+    pub fn scope(&self) -> DependencyScope {
+        match self {
+            Dependency::Version(_) => DependencyScope::default(),
+            Dependency::Detailed(d) => d.scope.unwrap_or_default(),
+        }
+    }
+
+    pub fn exclude(&self) -> &[String] {
+        match self {
+            Dependency::Version(_) => &[],
+            Dependency::Detailed(d) => d.exclude.as_deref().unwrap_or_default(),
+        }
+    }
+
+    /// Хвост координат для classifier и ext: ":natives@aar", пусто если их нет
+    pub fn coords_suffix(&self) -> String {
+        let Dependency::Detailed(d) = self else {
+            return String::new();
+        };
+
+        let mut suffix = String::new();
+
+        if let Some(classifier) = &d.classifier {
+            suffix.push_str(&format!(":{}", classifier));
+        }
+
+        if let Some(ext) = &d.ext {
+            suffix.push_str(&format!("@{}", ext));
+        }
+
+        suffix
+    }
+
+    /// Нужна ровно одна из version и path. Путь делается относительным к
+    /// папке файла конфига, ведь module.toml лежат в подпапках
+    fn normalize(&mut self, name: &str, config_dir: &Path, config_path: &Path) -> Result<(), String> {
+        let Dependency::Detailed(details) = self else {
+            return Ok(());
+        };
+
+        match (&details.version, &details.path) {
+            (Some(_), None) => Ok(()),
+
+            (None, Some(_)) if details.exclude.is_some() => Err(format!(
+                "{}: dependency \"{}\" is a local file, exclude does not apply to it",
+                config_path.display(),
+                name
+            )),
+
+            (None, Some(_)) if details.classifier.is_some() || details.ext.is_some() => Err(format!(
+                "{}: dependency \"{}\" is a local file, classifier and ext do not apply to it",
+                config_path.display(),
+                name
+            )),
+
+            (None, Some(path)) => {
+                details.path = Some(config_dir.join(path).to_string_lossy().into_owned());
+                Ok(())
+            }
+
+            _ => Err(format!(
+                "{}: dependency \"{}\" needs either version or path",
+                config_path.display(),
+                name
+            )),
+        }
+    }
+}
+
+/// Замена артефакта в [substitutions]: false убирает его из графа,
+/// "group:name:version" (или "group:name" с той же версией) подставляет
+/// другой, { path = "libs/foo.aar" } локальный файл
+#[derive(Debug, Deserialize, Clone)]
+#[serde(untagged)]
+pub enum Substitution {
+    Remove(bool),
+    Artifact(String),
+    Local(LocalSubstitution),
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct LocalSubstitution {
+    /// Как у path зависимостей, после загрузки конфига путь от текущей папки
+    pub path: String,
+}
+
+impl Substitution {
+    fn normalize(&mut self, name: &str, config_dir: &Path, config_path: &Path) -> Result<(), String> {
+        match self {
+            Substitution::Remove(true) => Err(format!(
+                "{}: substitution \"{}\" = true, use false to remove it or a replacement",
+                config_path.display(),
+                name
+            )),
+
+            Substitution::Local(local) => {
+                local.path = config_dir.join(&local.path).to_string_lossy().into_owned();
+                Ok(())
+            }
+
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Секция [network]: повторные попытки и таймауты запросов к репозиториям
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct NetworkConfig {
+    /// Сколько раз повторять запрос при 5xx и сетевых ошибках, по умолчанию 3
+    pub retries: Option<u32>,
+
+    /// Задержка перед первым повтором в миллисекундах, дальше удваивается
+    pub backoff: Option<u64>,
+
+    /// Таймаут одного запроса в секундах, по умолчанию 30
+    pub timeout: Option<u64>,
+}
+
 /// [WAIT DOC]
 pub fn load_configs(paths: Vec<String>) -> Result<Config, Box<dyn std::error::Error>> {
     let first_toml = PathBuf::from(&paths[0]);
     
+    // Для "spawn.toml" parent() возвращает пустой путь, а не "."
     let base_dir = first_toml.parent()
+        .filter(|p| !p.as_os_str().is_empty())
         .unwrap_or(Path::new("."))
         .to_path_buf();
 
@@ -73,23 +441,47 @@ pub fn load_configs(paths: Vec<String>) -> Result<Config, Box<dyn std::error::Er
         ..Default::default()
     };
 
-    let mut all_deps = HashMap::new();
+    let mut all_deps = IndexMap::new();
     let mut all_repos = Vec::new();
 
     for (index, path) in target_paths.iter().enumerate() {
+        debug!("Loading config {}", path.display());
+
         let content = fs::read_to_string(path)?;
         let parsed: Config = toml::from_str(&content)?;
 
+        let config_dir = path.parent().unwrap_or(Path::new(""));
+
         if index == 0 {
             final_config.package = parsed.package;
             final_config.sign = parsed.sign;
+            final_config.variants = parsed.variants;
+            final_config.verification = parsed.verification;
+            final_config.network = parsed.network;
+            final_config.resolution = parsed.resolution;
+            final_config.default_repositories = parsed.default_repositories;
+            final_config.maven_local = parsed.maven_local;
+            final_config.constraints = parsed.constraints;
+            final_config.substitutions = parsed.substitutions;
+
+            for (name, substitution) in final_config.substitutions.iter_mut().flatten() {
+                substitution.normalize(name, config_dir, path)?;
+            }
         }
 
-        if let Some(deps) = parsed.dependencies {
+        if let Some(mut deps) = parsed.dependencies {
+            for (name, dep) in &mut deps {
+                dep.normalize(name, config_dir, path)?;
+            }
+
             all_deps.extend(deps);
         }
         
         if let Some(repos) = parsed.repositories {
+            for repo in &repos {
+                repo.check_no_credentials(path)?;
+            }
+
             all_repos.extend(repos);
         }
     }
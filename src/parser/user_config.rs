@@ -0,0 +1,77 @@
+// Copyright (c) 2025 Spawn
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// https://www.eclipse.org/legal/epl-2.0/
+// SPDX-License-Identifier: EPL-2.0
+
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+
+use dirs::home_dir;
+use serde::Deserialize;
+
+/// ~/.spawn/config.toml: настройки машины пользователя, а не проекта
+/// (зеркала, прокси, корпоративные сертификаты)
+#[derive(Debug, Deserialize, Default)]
+pub struct UserConfig {
+    #[serde(default)]
+    pub mirrors: Vec<MirrorConfig>,
+
+    pub proxy: Option<ProxyConfig>,
+    pub tls: Option<TlsConfig>,
+}
+
+/// [[mirrors]]: репозиторий который подменяет собой другие, как <mirrorOf>
+/// в settings.xml у Maven
+#[derive(Debug, Deserialize, Clone)]
+pub struct MirrorConfig {
+    /// По имени ищутся учётные данные, как у [[repositories]]
+    pub name: String,
+    pub url: String,
+
+    /// Какие репозитории подменять: "*", "external:*", "google,central",
+    /// "*,!nexus". Сравнивается с именем репозитория или его URL
+    pub mirror_of: String,
+}
+
+/// [proxy]. Если секции нет, то используются HTTPS_PROXY / HTTP_PROXY / NO_PROXY
+#[derive(Debug, Deserialize, Clone)]
+pub struct ProxyConfig {
+    /// http://host:port, можно с user:password@
+    pub url: String,
+
+    /// Хосты без прокси, дополняют NO_PROXY: ["localhost", ".corp.local"]
+    #[serde(default)]
+    pub no_proxy: Vec<String>,
+}
+
+/// [tls]
+#[derive(Debug, Deserialize, Clone)]
+pub struct TlsConfig {
+    /// PEM файл с дополнительными корневыми сертификатами
+    pub ca_bundle: Option<PathBuf>,
+}
+
+pub const USER_CONFIG_FILE: &str = "config.toml";
+
+/// Читает ~/.spawn/config.toml. Если файла нет, то настройки пустые
+pub fn load() -> Result<UserConfig, Box<dyn Error>> {
+    let Some(path) = home_dir().map(|h| h.join(".spawn").join(USER_CONFIG_FILE)) else {
+        return Ok(UserConfig::default());
+    };
+
+    if !path.exists() {
+        return Ok(UserConfig::default());
+    }
+
+    debug!("Loading user config {}", path.display());
+
+    let content = fs::read_to_string(&path)?;
+
+    // Без фрагмента файла в тексте ошибки, там может быть пароль от прокси
+    let config: UserConfig = toml::from_str(&content)
+        .map_err(|e| format!("Invalid {}: {}", path.display(), e.message()))?;
+
+    Ok(config)
+}
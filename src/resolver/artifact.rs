@@ -4,11 +4,20 @@
 // https://www.eclipse.org/legal/epl-2.0/
 // SPDX-License-Identifier: EPL-2.0
 
+use crate::resolver::version::VersionRange;
+
 #[derive(Debug, Clone, Eq, PartialEq, Hash)]
 pub struct Artifact {
     pub group: String,
     pub name: String,
     pub version: String,
+
+    /// sources, natives-android и т.п. Сам pom у артефакта с classifier общий
+    pub classifier: Option<String>,
+
+    /// Расширение файла если оно задано явно (@aar в координатах или
+    /// <type> в pom). Иначе его определяет <packaging> из pom
+    pub extension: Option<String>,
 }
 
 impl Artifact {
@@ -23,40 +32,95 @@ impl Artifact {
                 group: group.trim().to_string(),
                 name: "INVALID".to_string(),
                 version: version.to_string(),
+                classifier: None,
+                extension: None,
             }
         } else {
-            // В maven в названии версии бывают квадратные скобки что мешает
-            // разрешить зависимость, поэтому нужно их убрать. Например [1.6.1]
-            // превращается в 1.6.1
-            let clean_version = version.trim_matches(|c| c == '[' || c == ']').to_string();
-
+            // Диапазоны ([1.6.1], [1.0,2.0)) остаются как есть, версию по ним
+            // выбирает resolver
             Self {
                 group: group.trim().to_string(),
                 name: name.trim().to_string(),
-                version: clean_version,
+                version: version.trim().to_string(),
+                classifier: None,
+                extension: None,
             }
         }
     }
 
-    pub fn from_coords(coords: &str) -> Option<Self> {
-        let parts: Vec<&str> = coords.split(':').collect();
-        if parts.len() < 3 {
-            return None;
+    /// group:name:version, group:name:version:classifier, оба варианта
+    /// могут заканчиваться на @ext. Ошибка объясняет что не так с координатами
+    pub fn from_coords(coords: &str) -> Result<Self, String> {
+        let expected = || "expected group:name:version[:classifier][@ext]".to_string();
+
+        let (coords, extension) = match coords.rsplit_once('@') {
+            Some((coords, ext)) if !ext.trim().is_empty() => (coords, Some(ext.trim())),
+            Some(_) => return Err("empty extension after @".to_string()),
+            None => (coords, None),
+        };
+
+        let parts: Vec<&str> = coords.split(':').map(str::trim).collect();
+        if parts.len() < 3 || parts.len() > 4 || parts[0].is_empty() || parts[1].is_empty() {
+            return Err(expected());
+        }
+
+        if parts[2].is_empty() {
+            return Err("no version".to_string());
         }
 
-        Some(Self::new(parts[0], parts[1], parts[2]))
+        let mut art = Self::new(parts[0], parts[1], parts[2]);
+        art.classifier = parts.get(3).filter(|c| !c.is_empty()).map(|c| c.to_string());
+        art.extension = extension.map(|e| e.to_string());
+
+        Ok(art)
     }
 
+    /// Применяет <type> зависимости из pom. jar это тип по умолчанию, он
+    /// ничего не меняет
+    pub fn set_type(&mut self, entry_type: &str) {
+        match entry_type {
+            "" | "jar" => {}
+
+            "test-jar" => {
+                self.classifier.get_or_insert_with(|| "tests".to_string());
+                self.extension = Some("jar".to_string());
+            }
+
+            other => self.extension = Some(extension_for(other).to_string()),
+        }
+    }
+
+    /// group:name, а для артефакта с classifier group:name:classifier. Как и
+    /// в Maven, это разные зависимости со своим выбором версии
     pub fn id(&self) -> String {
+        match &self.classifier {
+            Some(classifier) => format!("{}:{}:{}", self.group, self.name, classifier),
+            None => self.module_id(),
+        }
+    }
+
+    /// group:name без classifier, по нему применяются правила из spawn.toml
+    pub fn module_id(&self) -> String {
         format!("{}:{}", self.group, self.name)
     }
 
     pub fn get_path(&self, ext: &str) -> String {
         let g = self.group.replace('.', "/");
-        format!(
-            "{}/{}/{}/{}-{}.{}",
-            g, self.name, self.version, self.name, self.version, ext
-        )
+        format!("{}/{}/{}/{}", g, self.name, self.version, self.file_name(&self.version, ext))
+    }
+
+    /// Имя файла в репозитории. version отличается от self.version у
+    /// snapshot (там версия с временной меткой). Classifier есть только у
+    /// файлов артефакта, pom и .module у них общие
+    pub fn file_name(&self, version: &str, ext: &str) -> String {
+        match self.file_classifier(ext) {
+            Some(classifier) => format!("{}-{}-{}.{}", self.name, version, classifier, ext),
+            None => format!("{}-{}.{}", self.name, version, ext),
+        }
+    }
+
+    pub fn file_classifier(&self, ext: &str) -> Option<&str> {
+        self.classifier.as_deref().filter(|_| ext != "pom" && ext != "module")
     }
 
     pub fn is_snapshot(&self) -> bool {
@@ -67,8 +131,7 @@ impl Artifact {
         let v = &self.version;
         v == "LATEST"
             || v == "RELEASE"
-            || v.contains('[')
-            || v.contains('(')
+            || VersionRange::is_range(v)
             || v.contains(',')
     }
 
@@ -84,6 +147,38 @@ impl Artifact {
 
 impl std::fmt::Display for Artifact {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}:{}:{}", self.group, self.name, self.version)
+        write!(f, "{}:{}:{}", self.group, self.name, self.version)?;
+
+        if let Some(classifier) = &self.classifier {
+            write!(f, ":{}", classifier)?;
+        }
+
+        if let Some(extension) = &self.extension {
+            write!(f, "@{}", extension)?;
+        }
+
+        Ok(())
     }
-}
\ No newline at end of file
+}
+
+/// Расширение файла для <packaging> или <type> из pom. pom означает что
+/// своего файла у артефакта нет
+pub fn extension_for(packaging: &str) -> &str {
+    match packaging {
+        "bundle" | "ejb" | "maven-plugin" | "eclipse-plugin" | "test-jar" => "jar",
+        other => other,
+    }
+}
+
+/// "*" подходит всем, "com.google.*" группе com.google и всем вложенным,
+/// остальное сравнивается точно
+pub fn group_matches(pattern: &str, group: &str) -> bool {
+    if pattern == "*" {
+        return true;
+    }
+
+    match pattern.strip_suffix(".*") {
+        Some(prefix) => group == prefix || group.starts_with(&format!("{}.", prefix)),
+        None => pattern == group,
+    }
+}
@@ -0,0 +1,80 @@
+// Copyright (c) 2025 Spawn
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// https://www.eclipse.org/legal/epl-2.0/
+// SPDX-License-Identifier: EPL-2.0
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use serde::Deserialize;
+use sha1::Sha1;
+use sha2::{Digest, Sha256, Sha512};
+
+/// Что делать если репозиторий не публикует контрольные суммы для файла
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChecksumPolicy {
+    /// Файл без контрольной суммы считается не найденным
+    #[default]
+    Strict,
+
+    /// Файл без контрольной суммы принимается с предупреждением
+    Lenient,
+}
+
+/// Алгоритмы в порядке предпочтения, от самого надёжного к самому слабому
+#[derive(Debug, Clone, Copy)]
+pub enum Algorithm {
+    Sha512,
+    Sha256,
+    Sha1,
+}
+
+pub const ALGORITHMS: [Algorithm; 3] = [Algorithm::Sha512, Algorithm::Sha256, Algorithm::Sha1];
+
+impl Algorithm {
+    /// Расширение файла рядом с артефактом (foo.jar.sha256)
+    pub fn ext(&self) -> &'static str {
+        match self {
+            Algorithm::Sha512 => "sha512",
+            Algorithm::Sha256 => "sha256",
+            Algorithm::Sha1 => "sha1",
+        }
+    }
+
+    /// Хэш файла, читается потоком без загрузки целиком в память
+    pub fn digest_file(&self, path: &Path) -> io::Result<String> {
+        let mut file = fs::File::open(path)?;
+
+        match self {
+            Algorithm::Sha512 => hash_reader::<Sha512>(&mut file),
+            Algorithm::Sha256 => hash_reader::<Sha256>(&mut file),
+            Algorithm::Sha1 => hash_reader::<Sha1>(&mut file),
+        }
+    }
+}
+
+fn hash_reader<D: Digest + io::Write>(reader: &mut impl io::Read) -> io::Result<String> {
+    let mut hasher = D::new();
+    io::copy(reader, &mut hasher)?;
+
+    Ok(hasher.finalize().iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+/// Достаёт хэш из содержимого файла контрольной суммы. Репозитории пишут его
+/// по-разному: только хэш, "хэш  имя_файла" или "имя_файла: хэш", поэтому
+/// берётся первое слово которое похоже на hex нужной длины
+pub fn parse_checksum(text: &str, algorithm: Algorithm) -> Option<String> {
+    let len = match algorithm {
+        Algorithm::Sha512 => 128,
+        Algorithm::Sha256 => 64,
+        Algorithm::Sha1 => 40,
+    };
+
+    text.split_whitespace()
+        .map(|w| w.trim_end_matches(':'))
+        .find(|w| w.len() == len && w.chars().all(|c| c.is_ascii_hexdigit()))
+        .map(|w| w.to_ascii_lowercase())
+}
@@ -0,0 +1,218 @@
+// Copyright (c) 2025 Spawn
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// https://www.eclipse.org/legal/epl-2.0/
+// SPDX-License-Identifier: EPL-2.0
+
+use std::fs::{self, File, OpenOptions};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+use reqwest::StatusCode;
+
+use crate::resolver::http::Http;
+use crate::resolver::repository::{file_url_path, Repository};
+
+/// Недокачанный файл меньше этого размера скачивается заново, а не докачивается
+const RESUME_MIN_BYTES: u64 = 1024 * 1024;
+
+/// Номер временного файла write_atomic внутри процесса
+static TMP_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// Эксклюзивная блокировка файла в кэше, общая для всех процессов spawn.
+/// Снимается когда значение выходит из области видимости
+pub struct CacheLock {
+    file: File,
+    path: PathBuf,
+}
+
+impl Drop for CacheLock {
+    /// path.lock удаляется пока блокировка ещё держится, поэтому в кэше
+    /// они не копятся. Кто ждал на удалённом файле, откроет новый
+    fn drop(&mut self) {
+        if cfg!(unix) {
+            fs::remove_file(&self.path).ok();
+        }
+
+        self.file.unlock().ok();
+    }
+}
+
+/// Ждёт пока другой процесс (или поток) закончит работу с файлом и
+/// блокирует его. Блокировка держится на path.lock рядом с файлом
+pub fn lock(path: &Path) -> io::Result<CacheLock> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let lock_path = sidecar(path, "lock");
+
+    loop {
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&lock_path)?;
+
+        file.lock()?;
+
+        // Пока мы ждали, прошлый владелец мог удалить path.lock. Тогда
+        // блокировка взята на файле которого уже нет, пробуем заново
+        if is_same_file(&file, &lock_path) {
+            return Ok(CacheLock { file, path: lock_path });
+        }
+    }
+}
+
+#[cfg(unix)]
+fn is_same_file(file: &File, path: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+
+    match (file.metadata(), fs::metadata(path)) {
+        (Ok(a), Ok(b)) => a.dev() == b.dev() && a.ino() == b.ino(),
+        _ => false,
+    }
+}
+
+/// Вне unix path.lock не удаляется, файл всегда тот же
+#[cfg(not(unix))]
+fn is_same_file(_file: &File, _path: &Path) -> bool {
+    true
+}
+
+/// Недокачанный файл: path.part
+fn part_path(path: &Path) -> PathBuf {
+    sidecar(path, "part")
+}
+
+/// Скачивает url в path.part потоком. Если от прошлой попытки остался
+/// достаточно большой .part, то запрашивается только недостающий хвост
+/// (Range), а если сервер Range не поддерживает, то файл качается заново.
+/// Оборванная передача повторяется с докачкой. Ok(None) значит что в этом
+/// репозитории файла нет. Из file:// репозитория файл просто копируется
+pub fn download(http: &Http, repo: &Repository, url: &str, path: &Path) -> Result<Option<PathBuf>, String> {
+    let part = part_path(path);
+
+    if let Some(source) = file_url_path(url) {
+        if !source.is_file() {
+            return Ok(None);
+        }
+
+        fs::copy(&source, &part).map_err(|e| format!("{}: {}", source.display(), e))?;
+        return Ok(Some(part));
+    }
+
+    let mut attempt = 0;
+
+    loop {
+        let offset = fs::metadata(&part)
+            .map(|m| m.len())
+            .ok()
+            .filter(|len| *len >= RESUME_MIN_BYTES)
+            .unwrap_or(0);
+
+        if offset > 0 {
+            debug!("Resuming {} from byte {}", url, offset);
+        }
+
+        let Some(mut resp) = http.get_from(repo, url, offset)? else {
+            return Ok(None);
+        };
+
+        let mut out = match resp.status() {
+            StatusCode::PARTIAL_CONTENT if offset > 0 => OpenOptions::new().append(true).open(&part),
+
+            // .part уже целиком скачан, проверка контрольной суммы решит что с ним делать
+            StatusCode::RANGE_NOT_SATISFIABLE => return Ok(Some(part)),
+
+            _ => File::create(&part),
+        }
+        .map_err(|e| e.to_string())?;
+
+        match resp.copy_to(&mut out) {
+            Ok(_) => {
+                out.sync_all().map_err(|e| e.to_string())?;
+                return Ok(Some(part));
+            }
+
+            Err(e) if attempt < http.retries() => {
+                let delay = http.backoff(attempt);
+                debug!("Download of {} interrupted: {}, retrying in {} ms", url, e, delay.as_millis());
+
+                thread::sleep(delay);
+                attempt += 1;
+            }
+
+            Err(e) => return Err(format!("Download of {} interrupted: {}", url, e)),
+        }
+    }
+}
+
+/// Переносит готовый файл на место одной операцией, поэтому в кэше никогда
+/// не появляется наполовину записанный файл
+pub fn commit(part: &Path, path: &Path) -> io::Result<()> {
+    fs::rename(part, path)
+}
+
+/// Записывает небольшой файл (метаданные, подпись) через временный файл и rename
+pub fn write_atomic(path: &Path, contents: impl AsRef<[u8]>) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    // Один и тот же файл могут писать несколько потоков rayon, у каждой
+    // записи свой временный файл
+    let n = TMP_COUNTER.fetch_add(1, Ordering::Relaxed);
+    let tmp = sidecar(path, &format!("tmp-{}-{}", std::process::id(), n));
+
+    fs::write(&tmp, contents)?;
+    fs::rename(&tmp, path)
+}
+
+fn sidecar(path: &Path, ext: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".");
+    name.push(ext);
+    PathBuf::from(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lock_is_removed_and_writes_do_not_collide() {
+        let dir = std::env::temp_dir().join(format!("spawn-test-download-{}", std::process::id()));
+        fs::remove_dir_all(&dir).ok();
+
+        let path = dir.join("maven-metadata.xml");
+
+        thread::scope(|s| {
+            for i in 0..8 {
+                let path = &path;
+
+                // Половина потоков пишет без блокировки, как write_atomic для sidecar файлов
+                s.spawn(move || {
+                    let _lock = (i % 2 == 0).then(|| lock(path).unwrap());
+                    write_atomic(path, format!("<metadata>{}</metadata>", i)).unwrap();
+                });
+            }
+        });
+
+        let mut names: Vec<String> = fs::read_dir(&dir).unwrap()
+            .map(|e| e.unwrap().file_name().into_string().unwrap())
+            .collect();
+        names.sort();
+
+        let content = fs::read_to_string(&path).unwrap();
+        fs::remove_dir_all(&dir).ok();
+
+        assert!(content.starts_with("<metadata>"));
+
+        if cfg!(unix) {
+            assert_eq!(names, vec!["maven-metadata.xml"]);
+        }
+    }
+}
@@ -0,0 +1,182 @@
+// Copyright (c) 2025 Spawn
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// https://www.eclipse.org/legal/epl-2.0/
+// SPDX-License-Identifier: EPL-2.0
+
+use std::fmt;
+
+use crate::resolver::artifact::Artifact;
+
+/// Почему не удалось получить артефакт или его метаданные
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ResolveError {
+    /// Файла нет ни в одном репозитории. unavailable это репозитории
+    /// которые не ответили, файл мог быть там
+    NotFound { path: String, unavailable: Vec<String> },
+
+    /// Группу не обслуживает ни один репозиторий (из-за include/exclude)
+    NoRepository { group: String, path: String },
+
+    /// Offline режим, а в кэше файла нет
+    NotCached { path: String },
+
+    ChecksumMismatch { url: String, algorithm: String, expected: String, actual: String },
+
+    /// Репозиторий не публикует контрольную сумму, а политика strict
+    MissingChecksum { url: String },
+
+    /// Репозиторий не ответил или передача оборвалась
+    Network(String),
+
+    /// Ошибка записи в кэш
+    Io(String),
+
+    /// pom или maven-metadata.xml не разбирается
+    Parse { path: String, reason: String },
+
+    /// Координаты зависимости из spawn.toml записаны с ошибкой
+    InvalidCoordinates { coords: String, reason: String },
+
+    /// Цепочка parent pom замкнулась сама на себя
+    Cycle(Vec<String>),
+
+    /// ${property} которого нет ни в pom, ни в его родителях
+    UnresolvedProperty { property: String },
+
+    /// Свойства pom ссылаются друг на друга по кругу
+    PropertyCycle(Vec<String>),
+
+    /// Ни одна опубликованная версия не подходит под диапазон
+    NoMatchingVersion { range: String },
+
+    /// Версия записана с ошибкой (например диапазон без закрывающей скобки)
+    InvalidVersion { version: String, reason: String },
+
+    /// Жёсткие требования к артефакту (диапазоны) несовместимы между собой.
+    /// Каждое требование вместе с тем кто его объявил
+    VersionConflict { requirements: Vec<String> },
+
+    /// Запрошено несколько версий, а стратегия конфликтов fail
+    ConflictingVersions { requests: Vec<String> },
+
+    /// Одну capability предоставляют разные артефакты, а стратегия
+    /// конфликтов fail
+    CapabilityConflict { capability: String, providers: Vec<String> },
+}
+
+impl fmt::Display for ResolveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ResolveError::NotFound { path, unavailable } if unavailable.is_empty() => {
+                write!(f, "not found: {}", path)
+            }
+
+            ResolveError::NotFound { path, unavailable } => {
+                write!(f, "not found: {} (unavailable repositories: {})", path, unavailable.join(", "))
+            }
+
+            ResolveError::NoRepository { group, path } => {
+                write!(f, "no repository serves group {}: {}", group, path)
+            }
+
+            ResolveError::NotCached { path } => write!(f, "not cached (offline): {}", path),
+
+            ResolveError::ChecksumMismatch { url, algorithm, expected, actual } => write!(
+                f,
+                "checksum mismatch for {} ({}): expected {}, got {}",
+                url, algorithm, expected, actual
+            ),
+
+            ResolveError::MissingChecksum { url } => write!(f, "no checksum published for {}", url),
+            ResolveError::Network(e) => write!(f, "network error: {}", e),
+            ResolveError::Io(e) => write!(f, "i/o error: {}", e),
+            ResolveError::Parse { path, reason } => write!(f, "cannot parse {}: {}", path, reason),
+
+            ResolveError::InvalidCoordinates { coords, reason } => {
+                write!(f, "invalid coordinates '{}' in [dependencies]: {}", coords, reason)
+            }
+
+            ResolveError::Cycle(chain) => write!(f, "parent cycle: {}", chain.join(" -> ")),
+            ResolveError::UnresolvedProperty { property } => write!(f, "unresolved property ${{{}}}", property),
+
+            ResolveError::PropertyCycle(chain) => {
+                let chain: Vec<String> = chain.iter().map(|p| format!("${{{}}}", p)).collect();
+                write!(f, "property cycle: {}", chain.join(" -> "))
+            }
+
+            ResolveError::NoMatchingVersion { range } => write!(f, "no version matches {}", range),
+
+            ResolveError::InvalidVersion { version, reason } => {
+                write!(f, "invalid version {}: {}", version, reason)
+            }
+
+            ResolveError::VersionConflict { requirements } => {
+                write!(f, "no version satisfies all of: {}", requirements.join(", "))
+            }
+
+            ResolveError::ConflictingVersions { requests } => {
+                write!(f, "conflicting versions: {}", requests.join(", "))
+            }
+
+            ResolveError::CapabilityConflict { capability, providers } => {
+                write!(f, "capability {} provided by: {}", capability, providers.join(", "))
+            }
+        }
+    }
+}
+
+impl std::error::Error for ResolveError {}
+
+impl ResolveError {
+    /// Файла просто нет (в отличие от битого файла или сбоя сети)
+    pub fn is_not_found(&self) -> bool {
+        matches!(
+            self,
+            ResolveError::NotFound { .. } | ResolveError::NoRepository { .. } | ResolveError::NotCached { .. }
+        )
+    }
+}
+
+/// Артефакт который не удалось разрешить или скачать
+#[derive(Debug, Clone)]
+pub struct ResolveFailure {
+    pub artifact: Artifact,
+
+    /// Цепочка зависимостей от корневой до artifact (без него самого).
+    /// Пустая если artifact объявлен в spawn.toml
+    pub path: Vec<Artifact>,
+
+    pub error: ResolveError,
+}
+
+impl fmt::Display for ResolveFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.error {
+            // Артефакт из таких координат собран как получилось, вместо него
+            // показываются сами координаты
+            ResolveError::InvalidCoordinates { .. } => write!(f, "{}", self.error)?,
+            _ => write!(f, "{}: {}", self.artifact, self.error)?,
+        }
+
+        if !self.path.is_empty() {
+            let chain: Vec<String> = self.path.iter()
+                .chain(std::iter::once(&self.artifact))
+                .map(|a| a.to_string())
+                .collect();
+
+            write!(f, "\n      path: {}", chain.join(" -> "))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Итоговый отчёт по всем проблемам с зависимостями
+pub fn report(failures: &[ResolveFailure]) -> String {
+    let lines: Vec<String> = failures.iter()
+        .map(|f| format!("  - {}", f))
+        .collect();
+
+    format!("{} dependency problem(s):\n{}", failures.len(), lines.join("\n"))
+}
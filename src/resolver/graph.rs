@@ -0,0 +1,663 @@
+// Copyright (c) 2025 Spawn
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// https://www.eclipse.org/legal/epl-2.0/
+// SPDX-License-Identifier: EPL-2.0
+
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::resolver::artifact::Artifact;
+use crate::resolver::error::ResolveError;
+use crate::resolver::module::Capability;
+use crate::resolver::version::{compare_versions, VersionRange};
+
+/// Как выбрать одну версию артефакта если в графе запрошено несколько
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConflictStrategy {
+    /// Самая новая из запрошенных (как в Gradle)
+    #[default]
+    Highest,
+
+    /// Ближайшая к корню, при равной глубине объявленная раньше (как в Maven)
+    Nearest,
+
+    /// Разные версии одного артефакта это ошибка
+    Fail,
+}
+
+impl fmt::Display for ConflictStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConflictStrategy::Highest => write!(f, "highest"),
+            ConflictStrategy::Nearest => write!(f, "nearest"),
+            ConflictStrategy::Fail => write!(f, "fail"),
+        }
+    }
+}
+
+/// Где нужна зависимость: compile и в classpath javac, и в APK, runtime
+/// только в APK, provided только в classpath, test только в classpath
+/// тестов, processor только в -processorpath
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Scope {
+    #[default]
+    Compile,
+    Runtime,
+    Provided,
+    Test,
+    Processor,
+}
+
+impl Scope {
+    /// Scope из pom. None для system и неизвестных
+    pub fn parse(scope: Option<&str>) -> Option<Self> {
+        match scope.unwrap_or_default() {
+            "" | "compile" => Some(Scope::Compile),
+            "runtime" => Some(Scope::Runtime),
+            "provided" => Some(Scope::Provided),
+            "test" => Some(Scope::Test),
+            _ => None,
+        }
+    }
+
+    /// Таблица Maven: каким становится scope транзитивной зависимости у
+    /// зависимости со scope self. None если она не нужна вовсе
+    pub fn transitive(self, dep: Scope) -> Option<Scope> {
+        match (self, dep) {
+            (_, Scope::Provided | Scope::Test) => None,
+            (Scope::Compile, dep) => Some(dep),
+            (scope, _) => Some(scope),
+        }
+    }
+
+    /// Scope артефакта который нужен по нескольким путям
+    pub fn merge(self, other: Scope) -> Scope {
+        match (self, other) {
+            (a, b) if a == b => a,
+            (Scope::Test | Scope::Processor, scope) | (scope, Scope::Test | Scope::Processor) => scope,
+
+            // В том числе runtime и provided: нужен и javac, и в APK
+            _ => Scope::Compile,
+        }
+    }
+
+    pub fn is_compile(&self) -> bool {
+        *self == Scope::Compile
+    }
+
+    /// Нужен ли в classpath javac и kotlinc
+    pub fn on_classpath(self) -> bool {
+        matches!(self, Scope::Compile | Scope::Provided)
+    }
+
+    /// Попадает ли в APK
+    pub fn packaged(self) -> bool {
+        matches!(self, Scope::Compile | Scope::Runtime)
+    }
+}
+
+/// Зависимость как она объявлена: конкретная версия и диапазон если это
+/// жёсткое требование
+#[derive(Debug, Clone)]
+pub struct Edge {
+    pub artifact: Artifact,
+    pub range: Option<VersionRange>,
+    pub scope: Scope,
+
+    /// Что не брать из поддерева этой зависимости
+    pub exclusions: Vec<Exclusion>,
+}
+
+/// Исключение транзитивной зависимости: group:name, group:* или просто group
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Exclusion {
+    pub group: String,
+    pub name: String,
+}
+
+impl Exclusion {
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let (group, name) = spec.split_once(':').unwrap_or((spec, "*"));
+
+        if group.is_empty() || name.is_empty() || name.contains(':') {
+            return Err(format!("invalid exclusion '{}', expected group:name", spec));
+        }
+
+        Ok(Self { group: group.to_string(), name: name.to_string() })
+    }
+
+    pub fn matches(&self, art: &Artifact) -> bool {
+        (self.group == "*" || self.group == art.group) && (self.name == "*" || self.name == art.name)
+    }
+}
+
+/// Проблема при обходе графа. owner это артефакт из-за которого она
+/// возникла (сам сломанный артефакт или тот кто его запросил), None для
+/// spawn.toml. Если owner не попал в итоговый граф, проблема не важна
+#[derive(Debug)]
+pub struct GraphFailure {
+    pub owner: Option<Artifact>,
+    pub artifact: Artifact,
+    pub error: ResolveError,
+}
+
+/// Все обойдённые версии вместе с их зависимостями, включая те что потом
+/// проиграют конфликт
+#[derive(Debug, Default)]
+pub struct Graph {
+    pub roots: Vec<Edge>,
+
+    /// Зависимости каждого обойдённого артефакта по group:name:version
+    pub edges: HashMap<String, Vec<Edge>>,
+
+    /// Обойдённые версии по group:name в порядке обхода
+    pub versions: HashMap<String, Vec<Artifact>>,
+
+    /// Диапазоны из [constraints] по group:name. Сами в граф ничего не
+    /// добавляют, только ограничивают выбор версии
+    pub constraints: HashMap<String, VersionRange>,
+
+    /// dependencyConstraints из .module по group:name:version владельца.
+    /// Участвуют в выборе версии только если артефакт и так есть в графе
+    pub published: HashMap<String, Vec<Edge>>,
+
+    /// capabilities из .module по group:name:version. Кого нет, тот
+    /// предоставляет только свой group:name
+    pub capabilities: HashMap<String, Vec<Capability>>,
+
+    /// group:name проигравших конфликт capabilities, в граф они не попадают
+    pub evicted: HashSet<String>,
+
+    pub failures: Vec<GraphFailure>,
+}
+
+/// Одна версия артефакта запрошенная в итоговом графе
+#[derive(Debug, Clone)]
+pub struct Request {
+    pub artifact: Artifact,
+    pub range: Option<VersionRange>,
+
+    /// Через какие артефакты пришёл запрос, пустой для spawn.toml
+    pub path: Vec<Artifact>,
+
+    /// Запрос из dependencyConstraints, а не из зависимостей
+    pub constraint: bool,
+}
+
+impl Request {
+    pub fn allows(&self, version: &str) -> bool {
+        self.range.as_ref().is_none_or(|r| r.contains(version))
+    }
+}
+
+impl fmt::Display for Request {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.range {
+            Some(range) => write!(f, "{}", range)?,
+            None => write!(f, "{}", self.artifact.version)?,
+        }
+
+        if self.constraint {
+            write!(f, " (constraint)")?;
+        }
+
+        if self.path.is_empty() {
+            write!(f, " from spawn.toml")
+        } else {
+            let chain: Vec<String> = self.path.iter().map(|a| a.to_string()).collect();
+            write!(f, " from {}", chain.join(" -> "))
+        }
+    }
+}
+
+/// Шаг обхода: зависимость, путь к ней, исключения накопленные по пути и
+/// scope того кто её запросил
+type Step<'a> = (&'a Edge, Vec<Artifact>, Vec<Exclusion>, Option<Scope>);
+
+/// С чем артефакт уже обойдён: scope для приложения (None если он нужен
+/// только процессору) и нужен ли он annotation processor
+struct NodeState {
+    exclusions: Vec<Exclusion>,
+    scope: Option<Scope>,
+    processor: bool,
+}
+
+/// Несколько выбранных артефактов предоставляют одну capability
+#[derive(Debug)]
+pub struct CapabilityConflict {
+    pub capability: String,
+
+    /// Артефакты с версией capability, первой идёт самая новая
+    pub providers: Vec<(Artifact, String)>,
+}
+
+/// Итог выбора версий
+#[derive(Debug, Default)]
+pub struct Selection {
+    /// Выбранная версия по group:name
+    pub selected: HashMap<String, Artifact>,
+
+    /// Цепочка от корня до каждого артефакта итогового графа (group:name:version)
+    pub paths: HashMap<String, Vec<Artifact>>,
+
+    /// Итоговый scope по group:name
+    pub scopes: HashMap<String, Scope>,
+
+    /// group:name артефактов которые нужны annotation processor
+    pub processors: HashSet<String>,
+
+    /// Все запросы по group:name из итогового графа
+    pub requests: BTreeMap<String, Vec<Request>>,
+
+    /// Артефакты для которых ни одна обойдённая версия не подходит под
+    /// диапазоны. Подходящую надо искать в репозитории
+    pub unsatisfied: Vec<String>,
+
+    /// Артефакты с несколькими версиями при стратегии fail
+    pub conflicts: Vec<String>,
+}
+
+impl Selection {
+    /// Запрошено больше одной версии (1.0 и 1.0.0 это одна версия)
+    pub fn is_conflict(&self, id: &str) -> bool {
+        self.requests.get(id).is_some_and(|requests| {
+            requests.iter().any(|r| compare_versions(&r.artifact.version, &requests[0].artifact.version) != Ordering::Equal)
+        })
+    }
+
+    /// Цепочка для проблемы из обхода: путь до owner и сам owner если
+    /// проблема у его зависимости. None если owner не в итоговом графе
+    pub fn failure_path(&self, failure: &GraphFailure) -> Option<Vec<Artifact>> {
+        let Some(owner) = &failure.owner else {
+            return Some(Vec::new());
+        };
+
+        let mut path = self.paths.get(&owner.to_string())?.clone();
+
+        if owner != &failure.artifact {
+            path.push(owner.clone());
+        }
+
+        Some(path)
+    }
+}
+
+impl Graph {
+    /// Выбирает версии и повторяет обход от корней уже только через
+    /// выбранные. Ветки которые тянули проигравшие версии перестают влиять
+    /// на выбор, поэтому повторяем пока выбор не перестанет меняться
+    pub fn select(&self, strategy: ConflictStrategy) -> Selection {
+        let mut winners: HashMap<String, Artifact> = HashMap::new();
+        let mut selection = Selection::default();
+
+        // Выбор почти всегда сходится за пару проходов, предел только от
+        // зацикливания на экзотических графах
+        for _ in 0..=self.versions.len() {
+            selection = self.walk(&winners);
+
+            for (id, requests) in &selection.requests {
+                match self.choose(id, requests, strategy) {
+                    Some(winner) => {
+                        selection.selected.insert(id.clone(), winner);
+                    }
+
+                    None => selection.unsatisfied.push(id.clone()),
+                }
+
+                if strategy == ConflictStrategy::Fail && selection.is_conflict(id) {
+                    selection.conflicts.push(id.clone());
+                }
+            }
+
+            if selection.selected == winners {
+                break;
+            }
+
+            winners = selection.selected.clone();
+        }
+
+        selection
+    }
+
+    /// Обход в ширину от корней. Для артефакта у которого уже выбрана
+    /// версия идём в неё, иначе в запрошенную. Артефакт исключён только
+    /// если он исключён на всех путях к нему, а scope берётся самый широкий.
+    /// Путь для annotation processor считается отдельно от scope: одна и
+    /// та же библиотека может быть нужна и приложению, и процессору
+    fn walk(&self, winners: &HashMap<String, Artifact>) -> Selection {
+        let mut selection = Selection::default();
+
+        // С чем артефакт уже обойдён. Если он встретился снова с меньшим
+        // набором исключений, более широким scope или впервые для
+        // процессора, его зависимости обходятся ещё раз
+        let mut visited: HashMap<String, NodeState> = HashMap::new();
+        let mut recorded = HashSet::new();
+        let mut reached = Vec::new();
+
+        // У корней scope свой, у остальных от того кто их запросил
+        let mut queue: Vec<Step> = self.roots.iter()
+            .map(|e| (e, Vec::new(), Vec::new(), None))
+            .collect();
+
+        while !queue.is_empty() {
+            let mut next_queue = Vec::new();
+
+            for (edge, path, inherited, parent_scope) in queue {
+                if inherited.iter().any(|e| e.matches(&edge.artifact)) || self.evicted.contains(&edge.artifact.id()) {
+                    continue;
+                }
+
+                let scope = match parent_scope {
+                    None => edge.scope,
+                    Some(parent) => match parent.transitive(edge.scope) {
+                        Some(scope) => scope,
+                        None => continue,
+                    },
+                };
+
+                let id = edge.artifact.id();
+                let parent = path.last().map(|p| p.to_string()).unwrap_or_default();
+
+                if recorded.insert((parent, edge.artifact.to_string())) {
+                    selection.requests.entry(id.clone()).or_default().push(Request {
+                        artifact: edge.artifact.clone(),
+                        range: edge.range.clone(),
+                        path: path.clone(),
+                        constraint: false,
+                    });
+                }
+
+                let node = winners.get(&id).unwrap_or(&edge.artifact);
+                let key = node.to_string();
+
+                let mut exclusions = inherited;
+                for e in &edge.exclusions {
+                    if !exclusions.contains(e) {
+                        exclusions.push(e.clone());
+                    }
+                }
+
+                let previous = visited.get(&key);
+
+                if previous.is_none() {
+                    selection.paths.insert(key.clone(), path.clone());
+                    reached.push(node.clone());
+                }
+
+                if let Some(previous) = previous {
+                    exclusions.retain(|e| previous.exclusions.contains(e));
+                }
+
+                let narrowed = previous.is_none_or(|p| p.exclusions.len() != exclusions.len());
+                let previous_scope = previous.and_then(|p| p.scope);
+                let previous_processor = previous.is_some_and(|p| p.processor);
+
+                let (app_scope, processor) = if scope == Scope::Processor {
+                    (previous_scope, true)
+                } else {
+                    (Some(previous_scope.map_or(scope, |p| p.merge(scope))), previous_processor)
+                };
+
+                let mut child_scopes = Vec::new();
+
+                if let Some(app_scope) = app_scope
+                    && (narrowed || previous_scope != Some(app_scope))
+                {
+                    child_scopes.push(app_scope);
+                }
+
+                if processor && (narrowed || !previous_processor) {
+                    child_scopes.push(Scope::Processor);
+                }
+
+                if child_scopes.is_empty() {
+                    continue;
+                }
+
+                visited.insert(key.clone(), NodeState { exclusions: exclusions.clone(), scope: app_scope, processor });
+
+                if let Some(app_scope) = app_scope {
+                    selection.scopes.entry(id.clone()).and_modify(|s| *s = s.merge(app_scope)).or_insert(app_scope);
+                }
+
+                if processor {
+                    selection.processors.insert(id);
+                }
+
+                let mut child_path = path;
+                child_path.push(node.clone());
+
+                for child in self.edges.get(&key).into_iter().flatten() {
+                    for child_scope in &child_scopes {
+                        next_queue.push((child, child_path.clone(), exclusions.clone(), Some(*child_scope)));
+                    }
+                }
+            }
+
+            queue = next_queue;
+        }
+
+        for owner in &reached {
+            let mut path = selection.paths[&owner.to_string()].clone();
+            path.push(owner.clone());
+
+            for edge in self.published.get(&owner.to_string()).into_iter().flatten() {
+                if let Some(requests) = selection.requests.get_mut(&edge.artifact.id()) {
+                    requests.push(Request {
+                        artifact: edge.artifact.clone(),
+                        range: edge.range.clone(),
+                        path: path.clone(),
+                        constraint: true,
+                    });
+                }
+            }
+        }
+
+        // Нужные только процессору
+        for id in &selection.processors {
+            selection.scopes.entry(id.clone()).or_insert(Scope::Processor);
+        }
+
+        selection
+    }
+
+    /// Capabilities которые предоставляет больше одного выбранного артефакта
+    pub fn capability_conflicts(&self, selection: &Selection) -> Vec<CapabilityConflict> {
+        let mut selected: Vec<&Artifact> = selection.selected.values().collect();
+        selected.sort_by_key(|a| a.id());
+
+        let mut providers: BTreeMap<String, Vec<(Artifact, String)>> = BTreeMap::new();
+
+        for art in selected {
+            match self.capabilities.get(&art.to_string()) {
+                Some(capabilities) => {
+                    for c in capabilities {
+                        providers.entry(c.id()).or_default().push((art.clone(), c.version.clone()));
+                    }
+                }
+
+                None => providers.entry(art.id()).or_default().push((art.clone(), art.version.clone())),
+            }
+        }
+
+        providers.into_iter()
+            .filter(|(_, p)| p.len() > 1)
+            .map(|(capability, mut providers)| {
+                providers.sort_by(|a, b| compare_versions(&b.1, &a.1));
+                CapabilityConflict { capability, providers }
+            })
+            .collect()
+    }
+
+    /// Подходит ли версия под все диапазоны артефакта, включая [constraints]
+    pub fn allows(&self, requests: &[Request], version: &str) -> bool {
+        requests.iter().all(|r| r.allows(version))
+            && requests.first().is_none_or(|r| {
+                self.constraints.get(&r.artifact.module_id()).is_none_or(|c| c.contains(version))
+            })
+    }
+
+    /// Версия которая подходит под все диапазоны. Сначала среди
+    /// запрошенных, потом среди любых обойдённых версий
+    fn choose(&self, id: &str, requests: &[Request], strategy: ConflictStrategy) -> Option<Artifact> {
+        let allowed = |v: &str| self.allows(requests, v);
+        let mut requested = requests.iter().map(|r| &r.artifact).filter(|a| allowed(&a.version));
+
+        let winner = match strategy {
+            // Запросы идут в порядке обхода в ширину, первый и есть ближайший
+            ConflictStrategy::Nearest => requested.next().cloned(),
+            ConflictStrategy::Highest | ConflictStrategy::Fail => highest(requested),
+        };
+
+        winner.or_else(|| {
+            let known = self.versions.get(id).into_iter().flatten().filter(|a| allowed(&a.version));
+            highest(known)
+        })
+    }
+}
+
+/// При равенстве (1.0 и 1.0.0) остаётся та что встретилась первой
+fn highest<'a>(versions: impl Iterator<Item = &'a Artifact>) -> Option<Artifact> {
+    versions
+        .reduce(|best, v| {
+            if compare_versions(&v.version, &best.version) == Ordering::Greater { v } else { best }
+        })
+        .cloned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn edge(coords: &str) -> Edge {
+        Edge {
+            artifact: Artifact::from_coords(coords).unwrap(),
+            range: None,
+            scope: Scope::Compile,
+            exclusions: Vec::new(),
+        }
+    }
+
+    /// Граф из корней и зависимостей по group:name:version владельца
+    fn graph(roots: &[&str], edges: &[(&str, &[&str])]) -> Graph {
+        let mut graph = Graph {
+            roots: roots.iter().map(|c| edge(c)).collect(),
+            edges: edges.iter()
+                .map(|(owner, deps)| (owner.to_string(), deps.iter().map(|c| edge(c)).collect()))
+                .collect(),
+            ..Default::default()
+        };
+
+        for e in graph.roots.iter().chain(graph.edges.values().flatten()) {
+            let versions = graph.versions.entry(e.artifact.id()).or_default();
+
+            if !versions.contains(&e.artifact) {
+                versions.push(e.artifact.clone());
+            }
+        }
+
+        graph
+    }
+
+    fn selected(graph: &Graph, strategy: ConflictStrategy, id: &str) -> Option<String> {
+        graph.select(strategy).selected.get(id).map(|a| a.version.clone())
+    }
+
+    /// c:1.0 ближе к корню, c:2.0 новее
+    fn diamond() -> Graph {
+        graph(
+            &["g:a:1.0", "g:b:1.0"],
+            &[
+                ("g:a:1.0", &["g:c:1.0"]),
+                ("g:b:1.0", &["g:x:1.0"]),
+                ("g:x:1.0", &["g:c:2.0"]),
+            ],
+        )
+    }
+
+    #[test]
+    fn highest_picks_newest() {
+        let graph = diamond();
+
+        assert_eq!(selected(&graph, ConflictStrategy::Highest, "g:c").as_deref(), Some("2.0"));
+        assert!(graph.select(ConflictStrategy::Highest).conflicts.is_empty());
+    }
+
+    #[test]
+    fn nearest_picks_closest_to_root() {
+        assert_eq!(selected(&diamond(), ConflictStrategy::Nearest, "g:c").as_deref(), Some("1.0"));
+    }
+
+    #[test]
+    fn nearest_tie_picks_declared_first() {
+        let edges: &[(&str, &[&str])] = &[("g:a:1.0", &["g:c:1.0"]), ("g:b:1.0", &["g:c:2.0"])];
+
+        let graph_ab = graph(&["g:a:1.0", "g:b:1.0"], edges);
+        let graph_ba = graph(&["g:b:1.0", "g:a:1.0"], edges);
+
+        assert_eq!(selected(&graph_ab, ConflictStrategy::Nearest, "g:c").as_deref(), Some("1.0"));
+        assert_eq!(selected(&graph_ba, ConflictStrategy::Nearest, "g:c").as_deref(), Some("2.0"));
+    }
+
+    #[test]
+    fn fail_reports_conflict() {
+        let selection = diamond().select(ConflictStrategy::Fail);
+        assert_eq!(selection.conflicts, vec!["g:c".to_string()]);
+
+        // 1.0 и 1.0.0 это одна версия
+        let graph = graph(&["g:a:1.0", "g:c:1.0.0"], &[("g:a:1.0", &["g:c:1.0"])]);
+        assert!(graph.select(ConflictStrategy::Fail).conflicts.is_empty());
+    }
+
+    #[test]
+    fn evicted_branch_is_pruned() {
+        // d нужна только проигравшей c:1.0
+        let graph = graph(
+            &["g:a:1.0", "g:c:2.0"],
+            &[
+                ("g:a:1.0", &["g:c:1.0"]),
+                ("g:c:1.0", &["g:d:1.0"]),
+            ],
+        );
+
+        let selection = graph.select(ConflictStrategy::Highest);
+
+        assert_eq!(selection.selected["g:c"].version, "2.0");
+        assert!(!selection.selected.contains_key("g:d"));
+    }
+
+    #[test]
+    fn capability_conflict_evicts_older() {
+        // new:2.0 заменяет old, у которой есть своя зависимость
+        let mut graph = graph(
+            &["g:old:1.0", "g:new:2.0"],
+            &[("g:old:1.0", &["g:dep:1.0"])],
+        );
+
+        graph.capabilities.insert("g:new:2.0".to_string(), vec![
+            Capability { group: "g".to_string(), name: "new".to_string(), version: "2.0".to_string() },
+            Capability { group: "g".to_string(), name: "old".to_string(), version: "2.0".to_string() },
+        ]);
+
+        let conflicts = graph.capability_conflicts(&graph.select(ConflictStrategy::Highest));
+
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].capability, "g:old");
+        assert_eq!(conflicts[0].providers[0].0.id(), "g:new");
+
+        graph.evicted.insert(conflicts[0].providers[1].0.id());
+
+        let selection = graph.select(ConflictStrategy::Highest);
+
+        assert!(selection.selected.contains_key("g:new"));
+        assert!(!selection.selected.contains_key("g:old"));
+        assert!(!selection.selected.contains_key("g:dep"));
+        assert!(graph.capability_conflicts(&selection).is_empty());
+    }
+}
@@ -0,0 +1,244 @@
+// Copyright (c) 2025 Spawn
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// https://www.eclipse.org/legal/epl-2.0/
+// SPDX-License-Identifier: EPL-2.0
+
+use std::collections::HashSet;
+use std::env;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+use reqwest::blocking::{Client, Response};
+use reqwest::header::RANGE;
+use reqwest::{Certificate, NoProxy, Proxy, StatusCode};
+
+use crate::logger;
+use crate::resolver::repository::{file_url_path, Repository};
+
+const DEFAULT_RETRIES: u32 = 3;
+const DEFAULT_BACKOFF: Duration = Duration::from_millis(500);
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Настройки HTTP клиента из [network] проекта и ~/.spawn/config.toml
+#[derive(Debug, Clone)]
+pub struct HttpSettings {
+    pub retries: u32,
+    pub backoff: Duration,
+    pub timeout: Duration,
+
+    /// Явный прокси. Если None, то reqwest сам берёт HTTPS_PROXY / HTTP_PROXY
+    pub proxy: Option<String>,
+
+    /// Хосты без прокси в дополнение к NO_PROXY
+    pub no_proxy: Vec<String>,
+
+    /// PEM файл с дополнительными корневыми сертификатами
+    pub ca_bundle: Option<PathBuf>,
+}
+
+impl Default for HttpSettings {
+    fn default() -> Self {
+        Self {
+            retries: DEFAULT_RETRIES,
+            backoff: DEFAULT_BACKOFF,
+            timeout: DEFAULT_TIMEOUT,
+            proxy: None,
+            no_proxy: Vec::new(),
+            ca_bundle: None,
+        }
+    }
+}
+
+/// HTTP клиент resolver с повторными попытками. Репозиторий который не ответил
+/// после всех попыток считается недоступным до конца запуска и больше не
+/// опрашивается, иначе каждый из сотен артефактов ждал бы его таймаут
+pub struct Http {
+    client: Client,
+    retries: u32,
+    backoff: Duration,
+    down: Mutex<HashSet<String>>,
+
+    /// Репозитории про отказ в доступе к которым уже предупредили
+    unauthorized: Mutex<HashSet<String>>,
+}
+
+impl Http {
+    pub fn new(settings: &HttpSettings) -> Result<Self, String> {
+        // На всякий слкчай клиент пытается косить под браузер
+        let mut builder = Client::builder()
+            .user_agent("Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36")
+            .timeout(settings.timeout)
+            .connect_timeout(settings.timeout.min(Duration::from_secs(10)))
+            .redirect(reqwest::redirect::Policy::default())
+            .hickory_dns(true);
+
+        if let Some(url) = &settings.proxy {
+            builder = builder.proxy(proxy(url, &settings.no_proxy)?);
+        }
+
+        if let Some(path) = &settings.ca_bundle {
+            let pem = fs::read(path).map_err(|e| format!("CA bundle {}: {}", path.display(), e))?;
+            let certs = Certificate::from_pem_bundle(&pem)
+                .map_err(|e| format!("CA bundle {}: {}", path.display(), e))?;
+
+            for cert in certs {
+                builder = builder.add_root_certificate(cert);
+            }
+        }
+
+        let client = builder.build().map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+
+        Ok(Self {
+            client,
+            retries: settings.retries,
+            backoff: settings.backoff,
+            down: Mutex::new(HashSet::new()),
+            unauthorized: Mutex::new(HashSet::new()),
+        })
+    }
+
+    pub fn is_down(&self, repo: &Repository) -> bool {
+        self.down.lock().unwrap().contains(&repo.url)
+    }
+
+    /// Репозитории помеченные недоступными за этот запуск
+    pub fn down_repositories(&self) -> Vec<String> {
+        let mut repos: Vec<String> = self.down.lock().unwrap().iter().cloned().collect();
+        repos.sort();
+        repos
+    }
+
+    /// GET url из репозитория repo. Ok(None) значит что файла нет (404 и прочие
+    /// 4xx), Err что репозиторий не ответил после всех попыток либо уже
+    /// помечен недоступным
+    pub fn get(&self, repo: &Repository, url: &str) -> Result<Option<Response>, String> {
+        self.get_from(repo, url, 0)
+    }
+
+    /// То же что get, но с заголовком Range: bytes=offset- если offset > 0.
+    /// Ответ 416 на такой запрос возвращается как есть
+    pub fn get_from(&self, repo: &Repository, url: &str, offset: u64) -> Result<Option<Response>, String> {
+        let mut attempt = 0;
+
+        loop {
+            if self.is_down(repo) {
+                return Err(format!("Repository {} is unavailable", repo.url));
+            }
+
+            let mut request = repo.authorize(self.client.get(url));
+
+            if offset > 0 {
+                request = request.header(RANGE, format!("bytes={}-", offset));
+            }
+
+            let error = match request.send() {
+                Ok(resp) if resp.status().is_success() => return Ok(Some(resp)),
+
+                Ok(resp) if offset > 0 && resp.status() == StatusCode::RANGE_NOT_SATISFIABLE => {
+                    return Ok(Some(resp));
+                }
+
+                Ok(resp) if is_retryable(resp.status()) => format!("HTTP {} for {}", resp.status(), url),
+
+                Ok(resp) if matches!(resp.status(), StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN) => {
+                    // Для приватного репозитория это почти всегда неверные учётные данные
+                    if let Some(name) = &repo.name
+                        && self.unauthorized.lock().unwrap().insert(repo.url.clone())
+                    {
+                        warn!("HTTP {} for {} (check credentials of '{}')", resp.status(), url, name);
+                    }
+
+                    debug!("HTTP {} for {}", resp.status(), url);
+
+                    return Ok(None);
+                }
+
+                Ok(resp) => {
+                    debug!("HTTP {} for {}", resp.status(), url);
+                    return Ok(None);
+                }
+
+                Err(e) => format!("{}: {}", url, e),
+            };
+
+            if attempt >= self.retries {
+                self.mark_down(repo, &error);
+                return Err(error);
+            }
+
+            let delay = self.backoff(attempt);
+            debug!("{}, retrying in {} ms", error, delay.as_millis());
+
+            thread::sleep(delay);
+            attempt += 1;
+        }
+    }
+
+    /// Содержимое небольшого файла (метаданные, контрольная сумма, подпись)
+    /// целиком. В отличие от get понимает file:// URL, Ok(None) значит что файла нет
+    pub fn fetch(&self, repo: &Repository, url: &str) -> Result<Option<Vec<u8>>, String> {
+        if let Some(path) = file_url_path(url) {
+            return match fs::read(&path) {
+                Ok(bytes) => Ok(Some(bytes)),
+                Err(e) if matches!(e.kind(), ErrorKind::NotFound | ErrorKind::IsADirectory) => Ok(None),
+                Err(e) => Err(format!("{}: {}", path.display(), e)),
+            };
+        }
+
+        let Some(resp) = self.get(repo, url)? else {
+            return Ok(None);
+        };
+
+        resp.bytes()
+            .map(|b| Some(b.to_vec()))
+            .map_err(|e| format!("Failed to read response from {}: {}", url, e))
+    }
+
+    /// Задержка перед повтором номер attempt (с нуля): backoff, 2*backoff, 4*backoff...
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        self.backoff.saturating_mul(1 << attempt.min(16))
+    }
+
+    pub fn retries(&self) -> u32 {
+        self.retries
+    }
+
+    fn mark_down(&self, repo: &Repository, error: &str) {
+        if self.down.lock().unwrap().insert(repo.url.clone()) {
+            warn!("Repository {} is unavailable ({}), skipping it for the rest of the run", repo.url, error);
+        }
+    }
+}
+
+/// Прокси для всех запросов. Исключения из NO_PROXY (или no_proxy) и из
+/// конфига объединяются. Пароль из user:password@ не попадает в вывод
+fn proxy(url: &str, no_proxy: &[String]) -> Result<Proxy, String> {
+    if let Some((_, rest)) = url.split_once("://")
+        && let Some((userinfo, _)) = rest.split_once('@')
+        && let Some((_, password)) = userinfo.split_once(':')
+    {
+        logger::add_secret(password);
+    }
+
+    let mut hosts: Vec<String> = ["NO_PROXY", "no_proxy"].iter()
+        .filter_map(|key| env::var(key).ok())
+        .flat_map(|v| v.split(',').map(|h| h.trim().to_string()).collect::<Vec<_>>())
+        .filter(|h| !h.is_empty())
+        .collect();
+
+    hosts.extend(no_proxy.iter().cloned());
+
+    let proxy = Proxy::all(url).map_err(|e| format!("Invalid proxy {}: {}", url, e))?;
+
+    Ok(proxy.no_proxy(NoProxy::from_string(&hosts.join(","))))
+}
+
+/// 5xx и 429 (слишком много запросов) это временные ошибки сервера
+fn is_retryable(status: StatusCode) -> bool {
+    status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS
+}
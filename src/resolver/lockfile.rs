@@ -0,0 +1,287 @@
+// Copyright (c) 2025 Spawn
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// https://www.eclipse.org/legal/epl-2.0/
+// SPDX-License-Identifier: EPL-2.0
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::resolver::artifact::Artifact;
+use crate::resolver::graph::Scope;
+use crate::resolver::Resolver;
+
+pub const LOCK_FILE: &str = "spawn.lock";
+
+const LOCK_VERSION: u32 = 1;
+
+const HEADER: &str = "# This file is generated by spawn, do not edit it manually.\n\
+                      # Run `spawn lock --update` to refresh it.\n\n";
+
+/// Содержимое spawn.lock. Файл коммитится в репозиторий проекта и фиксирует
+/// точный граф зависимостей (включая динамические версии и SNAPSHOT), чтобы
+/// сборка на разных машинах использовала одни и те же артефакты
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LockFile {
+    pub version: u32,
+
+    /// Отпечаток объявленных зависимостей и репозиториев. Если он не совпадает
+    /// с текущим конфигом, то lock устарел и граф разрешается заново
+    pub fingerprint: String,
+
+    #[serde(default, rename = "artifact")]
+    pub artifacts: Vec<LockedArtifact>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LockedArtifact {
+    pub group: String,
+    pub name: String,
+    pub version: String,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub classifier: Option<String>,
+
+    /// Расширение скачанного файла
+    pub packaging: String,
+
+    /// runtime, provided, test и processor, compile не пишется
+    #[serde(default, skip_serializing_if = "Scope::is_compile")]
+    pub scope: Scope,
+
+    /// Нужен ещё и annotation processor (если scope не processor)
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub processor: bool,
+
+    /// Для SNAPSHOT версий точная версия файла с временной меткой
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub snapshot: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub repository: Option<String>,
+
+    pub sha256: String,
+}
+
+impl LockedArtifact {
+    pub fn id(&self) -> String {
+        self.artifact().id()
+    }
+
+    pub fn module_id(&self) -> String {
+        format!("{}:{}", self.group, self.name)
+    }
+
+    pub fn artifact(&self) -> Artifact {
+        let mut art = Artifact::new(&self.group, &self.name, &self.version);
+        art.classifier = self.classifier.clone();
+
+        // Файл берётся ровно тот что был зафиксирован, без перебора aar/jar
+        art.extension = Some(self.packaging.clone());
+        art
+    }
+}
+
+impl LockFile {
+    /// Читает lock файл. Если файла нет, то возвращает None
+    pub fn load(path: &Path) -> Result<Option<Self>, Box<dyn Error>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let content = fs::read_to_string(path)?;
+        let lock: LockFile = toml::from_str(&content)
+            .map_err(|e| format!("Invalid {}: {}", path.display(), e))?;
+
+        if lock.version != LOCK_VERSION {
+            return Err(format!(
+                "Unsupported {} version {}, expected {}",
+                path.display(),
+                lock.version,
+                LOCK_VERSION
+            ).into());
+        }
+
+        Ok(Some(lock))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        let body = toml::to_string(self)?;
+        fs::write(path, format!("{}{}", HEADER, body))?;
+
+        Ok(())
+    }
+
+    /// Собирает lock из разрешённого и скачанного графа. Артефакты без своего
+    /// файла фиксируются по pom. Вызывать нужно после download_all: если
+    /// какого-то файла нет в кэше, то это ошибка, неполный lock не пишется
+    pub fn from_resolver(resolver: &Resolver, fingerprint: String) -> Result<Self, String> {
+        let mut artifacts = Vec::new();
+        let mut missing = Vec::new();
+
+        for art in resolver.resolved_artifacts.values() {
+            let Some((path, packaging)) = resolver.locked_file(art) else {
+                missing.push(art.to_string());
+                continue;
+            };
+
+            let Some(sha256) = resolver.artifact_sha256(&path) else {
+                missing.push(art.to_string());
+                continue;
+            };
+
+            artifacts.push(LockedArtifact {
+                group: art.group.clone(),
+                name: art.name.clone(),
+                version: art.version.clone(),
+                classifier: art.classifier.clone(),
+                packaging: packaging.clone(),
+                scope: resolver.scope(art),
+                processor: resolver.scope(art) != Scope::Processor && resolver.processors.contains(&art.id()),
+                snapshot: art.is_snapshot()
+                    .then(|| resolver.snapshot_version(art, &packaging))
+                    .flatten(),
+                repository: resolver.artifact_source(&path),
+                sha256,
+            });
+        }
+
+        if !missing.is_empty() {
+            missing.sort();
+            return Err(format!("Can't write {}, not in cache: {}", LOCK_FILE, missing.join(", ")));
+        }
+
+        artifacts.sort_by_key(|a| a.id());
+
+        Ok(Self {
+            version: LOCK_VERSION,
+            fingerprint,
+            artifacts,
+        })
+    }
+
+    /// Версии из lock по id артефакта кроме обновляемых group:name (вместе
+    /// со всеми их classifier). Используются чтобы зафиксировать остальное
+    /// при частичном обновлении
+    pub fn pins_except(&self, update: &[String]) -> Result<HashMap<String, String>, String> {
+        if let Some(id) = update.iter().find(|id| !self.artifacts.iter().any(|a| a.module_id() == **id)) {
+            return Err(format!("{} is not in {}", id, LOCK_FILE));
+        }
+
+        Ok(self.artifacts.iter()
+            .filter(|a| !update.contains(&a.module_id()))
+            .map(|a| (a.id(), a.version.clone()))
+            .collect())
+    }
+}
+
+fn is_false(value: &bool) -> bool {
+    !value
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn locked(group: &str, name: &str, version: &str, classifier: Option<&str>) -> LockedArtifact {
+        LockedArtifact {
+            group: group.to_string(),
+            name: name.to_string(),
+            version: version.to_string(),
+            classifier: classifier.map(String::from),
+            packaging: "jar".to_string(),
+            scope: Scope::default(),
+            processor: false,
+            snapshot: None,
+            repository: None,
+            sha256: "00".repeat(32),
+        }
+    }
+
+    fn lock(artifacts: Vec<LockedArtifact>) -> LockFile {
+        LockFile {
+            version: LOCK_VERSION,
+            fingerprint: "abc".to_string(),
+            artifacts,
+        }
+    }
+
+    fn temp_file(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("spawn-test-{}-{}.lock", name, std::process::id()))
+    }
+
+    #[test]
+    fn save_and_load() {
+        let mut aar = locked("androidx.core", "core", "1.12.0", None);
+        aar.packaging = "aar".to_string();
+        aar.scope = Scope::Runtime;
+        aar.repository = Some("https://maven.google.com/".to_string());
+
+        let mut natives = locked("org.example", "lib", "1.0-SNAPSHOT", Some("natives"));
+        natives.processor = true;
+        natives.snapshot = Some("1.0-20250101.120000-3".to_string());
+
+        let path = temp_file("roundtrip");
+        lock(vec![aar, natives]).save(&path).unwrap();
+
+        let content = fs::read_to_string(&path).unwrap();
+        let loaded = LockFile::load(&path).unwrap().unwrap();
+        fs::remove_file(&path).ok();
+
+        assert!(content.starts_with(HEADER));
+        assert_eq!(loaded.fingerprint, "abc");
+        assert_eq!(loaded.artifacts.len(), 2);
+
+        let aar = &loaded.artifacts[0];
+        assert_eq!(aar.id(), "androidx.core:core");
+        assert_eq!(aar.scope, Scope::Runtime);
+        assert_eq!(aar.repository.as_deref(), Some("https://maven.google.com/"));
+        assert_eq!(aar.artifact().extension.as_deref(), Some("aar"));
+
+        let natives = &loaded.artifacts[1];
+        assert_eq!(natives.id(), "org.example:lib:natives");
+        assert!(natives.processor);
+        assert_eq!(natives.snapshot.as_deref(), Some("1.0-20250101.120000-3"));
+        assert_eq!(natives.scope, Scope::default());
+
+        assert!(LockFile::load(&temp_file("missing")).unwrap().is_none());
+    }
+
+    #[test]
+    fn version_mismatch() {
+        let path = temp_file("version");
+        fs::write(&path, "version = 99\nfingerprint = \"abc\"\n").unwrap();
+
+        let error = LockFile::load(&path).unwrap_err().to_string();
+        fs::remove_file(&path).ok();
+
+        assert!(error.starts_with("Unsupported"), "{}", error);
+        assert!(error.ends_with("version 99, expected 1"), "{}", error);
+    }
+
+    #[test]
+    fn pins_except_updated() {
+        let lock = lock(vec![
+            locked("org.a", "lib", "1.0", None),
+            locked("org.a", "lib", "1.0", Some("sources")),
+            locked("org.b", "util", "2.0", None),
+        ]);
+
+        let pins = lock.pins_except(&["org.a:lib".to_string()]).unwrap();
+        assert_eq!(pins, HashMap::from([("org.b:util".to_string(), "2.0".to_string())]));
+
+        assert_eq!(lock.pins_except(&[]).unwrap().len(), 3);
+
+        assert_eq!(
+            lock.pins_except(&["org.c:other".to_string()]).unwrap_err(),
+            "org.c:other is not in spawn.lock"
+        );
+
+        // По group:name:classifier обновить нельзя, только весь group:name
+        assert!(lock.pins_except(&["org.a:lib:sources".to_string()]).is_err());
+    }
+}
@@ -5,290 +5,1023 @@
 // SPDX-License-Identifier: EPL-2.0
 
 pub mod artifact;
+pub mod checksum;
+mod download;
+pub mod error;
+pub mod graph;
+pub mod http;
+pub mod lockfile;
+pub mod module;
 pub mod pom;
-mod unpacker;
+pub mod repository;
+pub mod signature;
+pub mod unpacker;
+pub mod version;
 
 use std::collections::{HashMap, HashSet};
 use std::fs;
-use std::io::Write;
+use std::io;
 use std::path::{Path, PathBuf};
-use std::time::Duration;
-use std::cmp::Ordering;
 
-use colored::Colorize;
 use quick_xml::{events::Event, Reader};
 use rayon::prelude::*;
-use reqwest::blocking::Client;
 
-use crate::resolver::artifact::Artifact;
-use crate::resolver::pom::{parse as parse_pom, process_imports, resolve_val, Pom};
+use crate::resolver::artifact::{extension_for, Artifact};
+use crate::resolver::checksum::{Algorithm, ChecksumPolicy, ALGORITHMS, parse_checksum};
+use crate::resolver::error::{ResolveError, ResolveFailure};
+use crate::resolver::graph::{CapabilityConflict, ConflictStrategy, Edge, Exclusion, Graph, GraphFailure, Scope, Selection};
+use crate::resolver::http::{Http, HttpSettings};
+use crate::resolver::lockfile::LockedArtifact;
+use crate::resolver::module::{parse as parse_module, SelectedVariant, GRADLE_METADATA_MARKER};
+use crate::resolver::pom::{activate_profiles, interpolate_pom, parse as parse_pom, process_imports, DependencyEntry, Pom};
+use crate::resolver::repository::{apply_mirrors, file_url_path, Mirror, Repository};
+use crate::resolver::signature::{SignatureEntry, SignatureReport, SignatureVerifier};
+use crate::resolver::version::{compare_versions, VersionRange};
 
 pub struct Resolver {
     pub resolved_artifacts: HashMap<String, Artifact>,
     pub cache_root: PathBuf,
 
-    client: Client,
-    repositories: Vec<String>,
-}
+    /// Артефакты которые не удалось разрешить или скачать, с причиной и
+    /// цепочкой зависимостей которая к ним привела
+    pub unresolved: Vec<ResolveFailure>,
 
-impl Resolver {
-    pub fn new(user_repos: Vec<String>, cache_base: &Path) -> Self {
-        let cache_root = cache_base.join("repository");
-        fs::create_dir_all(&cache_root).ok();
+    /// Через какие зависимости артефакт (group:name:version) попал в граф
+    dependency_paths: HashMap<String, Vec<Artifact>>,
 
-        let mut repositories = vec![
-            "https://dl.google.com/dl/android/maven2/".to_string(),
-            "https://dl.google.com/android/maven2/".to_string(),
-            "https://repo1.maven.org/maven2/".to_string(),
+    /// Как выбирать версию если в графе их запрошено несколько
+    pub conflict_strategy: ConflictStrategy,
 
-            // Зеркало для РФ и Китая, обходит блокировки от гугла
-            "https://repo.huaweicloud.com/repository/maven/".to_string(),
-        ];
+    /// В offline режиме сеть не используется, всё берётся из кэша
+    pub offline: bool,
 
-        for r in user_repos {
-            let mut url = r.trim().to_string();
-            
-            if !url.ends_with('/') {
-                url.push('/');
-            }
+    /// Версии зафиксированные по group:name (из spawn.lock при частичном
+    /// обновлении). Такие артефакты не переразрешаются
+    pub pinned: HashMap<String, String>,
 
-            if !repositories.contains(&url) {
-                repositories.push(url);
-            }
-        }
+    /// Точные версии snapshot файлов по group:name:version из spawn.lock
+    pinned_snapshots: HashMap<String, String>,
+
+    /// Репозиторий из spawn.lock по id и версии артефакта. Файл сначала
+    /// ищется в нём, а уже потом в остальных
+    pinned_sources: HashMap<String, String>,
+
+    /// Версии из [constraints] по group:name. Заменяют любую запрошенную
+    /// версию, даже диапазон
+    pub forced: HashMap<String, String>,
+
+    /// Диапазоны из [constraints] по group:name. Выбранная версия обязана в
+    /// них попадать
+    pub constraints: HashMap<String, VersionRange>,
+
+    /// Замены из [substitutions] по group:name. None убирает артефакт из графа
+    pub substitutions: HashMap<String, Option<Artifact>>,
+
+    /// exclude корневых зависимостей по group:name
+    pub exclusions: HashMap<String, Vec<Exclusion>>,
+
+    /// scope корневых зависимостей по group:name, по умолчанию compile
+    pub root_scopes: HashMap<String, Scope>,
+
+    /// Scope разрешённых артефактов по group:name. Кого нет, тот compile
+    pub scopes: HashMap<String, Scope>,
 
-        // На всякий слкчай клиент пытается косить под браузер
-        let client = Client::builder()
-            .user_agent("Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36")
-            .timeout(Duration::from_secs(30))
-            .redirect(reqwest::redirect::Policy::default())
-            .hickory_dns(true)
-            .build()
-            .expect("Failed to create HTTP client");
+    /// group:name артефактов для -processorpath
+    pub processors: HashSet<String>,
+
+    /// Расширение файла по group:name:version из <packaging> или .module,
+    /// если оно не задано в самих координатах. pom значит что файла нет
+    packaging: HashMap<String, String>,
+
+    /// Что делать с файлами для которых репозиторий не публикует контрольные суммы
+    pub checksum_policy: ChecksumPolicy,
+
+    http: Http,
+    repositories: Vec<Repository>,
+    mirrors: Vec<Mirror>,
+}
+
+/// Версии из maven-metadata.xml
+struct VersionMetadata {
+    latest: String,
+    release: String,
+    versions: Vec<String>,
+}
+
+impl Resolver {
+    /// repositories это полный список в порядке опроса, вместе со встроенными
+    pub fn new(repositories: Vec<Repository>, cache_base: &Path) -> Self {
+        let cache_root = cache_base.join("repository");
+        fs::create_dir_all(&cache_root).ok();
 
         Self {
             resolved_artifacts: HashMap::new(),
-            client,
+            unresolved: Vec::new(),
+            dependency_paths: HashMap::new(),
+            conflict_strategy: ConflictStrategy::default(),
+            offline: false,
+            pinned: HashMap::new(),
+            pinned_snapshots: HashMap::new(),
+            pinned_sources: HashMap::new(),
+            forced: HashMap::new(),
+            constraints: HashMap::new(),
+            substitutions: HashMap::new(),
+            exclusions: HashMap::new(),
+            root_scopes: HashMap::new(),
+            scopes: HashMap::new(),
+            processors: HashSet::new(),
+            packaging: HashMap::new(),
+            checksum_policy: ChecksumPolicy::default(),
+            http: Http::new(&HttpSettings::default()).expect("Failed to create HTTP client"),
+            mirrors: Vec::new(),
             repositories,
             cache_root,
         }
     }
 
-    pub fn resolve(&mut self, root_coords: Vec<String>) {
-        let mut queue: Vec<Artifact> = root_coords
-            .iter()
-            .filter_map(|c| Artifact::from_coords(c))
+    /// Настройки сети: повторы и таймауты из [network], прокси и сертификаты
+    /// из ~/.spawn/config.toml
+    pub fn set_network(&mut self, settings: &HttpSettings) -> Result<(), String> {
+        self.http = Http::new(settings)?;
+        Ok(())
+    }
+
+    /// Зеркала из ~/.spawn/config.toml. Применяются к уже добавленным
+    /// репозиториям и к тем что встретятся в pom
+    pub fn set_mirrors(&mut self, mirrors: Vec<Mirror>) {
+        self.repositories = self.repositories.drain(..)
+            .map(|r| apply_mirrors(&mirrors, r))
             .collect();
 
-        let mut all_versions: HashMap<String, Vec<Artifact>> = HashMap::new();
-        let mut visited = HashSet::new();
+        self.mirrors = mirrors;
+    }
+
+    pub fn resolve(&mut self, root_coords: Vec<String>) {
+        let mut graph = Graph {
+            constraints: self.constraints.clone(),
+            ..Default::default()
+        };
+
+        let mut queue = Vec::new();
+
+        for coords in &root_coords {
+            // Такая зависимость объявлена в spawn.toml, путь к ней пустой
+            let art = match Artifact::from_coords(coords) {
+                Ok(art) => art,
+                Err(reason) => {
+                    let parts: Vec<&str> = coords.split(['@', ':']).collect();
+                    let part = |i: usize| parts.get(i).copied().unwrap_or_default();
+
+                    graph.failures.push(GraphFailure {
+                        owner: None,
+                        artifact: Artifact::new(part(0), part(1), part(2)),
+                        error: ResolveError::InvalidCoordinates { coords: coords.clone(), reason },
+                    });
+
+                    continue;
+                }
+            };
+
+            let exclusions = self.exclusions.get(&art.module_id()).cloned().unwrap_or_default();
+            let scope = self.root_scopes.get(&art.module_id()).copied().unwrap_or_default();
+
+            let Some(mut art) = self.substitute(art) else {
+                continue;
+            };
+
+            match self.select_version(&mut art) {
+                Ok(range) => {
+                    graph.roots.push(Edge { artifact: art.clone(), range, scope, exclusions });
+                    queue.push(art);
+                }
+
+                Err(error) => graph.failures.push(GraphFailure { owner: None, artifact: art, error }),
+            }
+        }
 
         // println!("{} Resolving graph", "TASK:".green());
         task!("Resolving graph");
 
+        // Диапазон может исключить все встреченные версии артефакта. Тогда
+        // в граф добавляется подходящая версия из репозитория и обход
+        // продолжается с неё
+        loop {
+            self.traverse(queue, &mut graph);
+
+            let selection = graph.select(self.conflict_strategy);
+            queue = Vec::new();
+
+            let mut conflicts = Vec::new();
+
+            for id in &selection.unsatisfied {
+                let requests = &selection.requests[id];
+                let first = &requests[0];
+
+                match self.highest_version(&first.artifact, |v| graph.allows(requests, v)) {
+                    Ok(version) => {
+                        debug!("No version of {} in graph satisfies its ranges, adding {}", id, version);
+
+                        let mut art = first.artifact.clone();
+                        art.version = version;
+                        queue.push(art);
+                    }
+
+                    Err(_) => conflicts.push(id.clone()),
+                }
+            }
+
+            // Версия из dependencyConstraints могла ещё не обходиться
+            for art in selection.selected.values() {
+                if !graph.edges.contains_key(&art.to_string()) {
+                    queue.push(art.clone());
+                }
+            }
+
+            if !queue.is_empty() {
+                continue;
+            }
+
+            let capability_conflicts = graph.capability_conflicts(&selection);
+
+            if capability_conflicts.is_empty() || self.conflict_strategy == ConflictStrategy::Fail {
+                self.apply_selection(&graph, selection, &conflicts, &capability_conflicts);
+                break;
+            }
+
+            // Остаётся артефакт с самой новой версией capability, остальные
+            // убираются из графа вместе со своими зависимостями
+            for conflict in capability_conflicts {
+                let (winner, _) = &conflict.providers[0];
+                note!(" Capability conflict for {}: selected {}", conflict.capability, winner);
+
+                for (loser, version) in &conflict.providers[1..] {
+                    info!("   {} ({})", loser, version);
+                    graph.evicted.insert(loser.id());
+                }
+            }
+        }
+    }
+
+    /// Обходит pom начиная с queue и добавляет в graph каждую новую версию
+    /// со всеми её зависимостями
+    fn traverse(&mut self, mut queue: Vec<Artifact>, graph: &mut Graph) {
         while !queue.is_empty() {
             let mut next_queue = Vec::new();
 
             for art in queue {
-                let id = art.id();
-                let versions = all_versions.entry(id.clone()).or_insert_with(Vec::new);
+                let key = art.to_string();
 
-                if versions.iter().any(|e| e.version == art.version) {
+                if graph.edges.contains_key(&key) {
                     continue;
                 }
 
-                versions.push(art.clone());
+                graph.edges.insert(key.clone(), Vec::new());
+                graph.versions.entry(art.id()).or_default().push(art.clone());
 
-                if let Ok(pom_path) = self.fetch_artifact(&art, "pom") {
-                    if let Ok(xml) = fs::read_to_string(pom_path) {
-                        let mut pom_data = self.get_effective_pom(&art, &xml);
+                let xml = self.fetch_artifact(&art, "pom")
+                    .and_then(|pom_path| fs::read_to_string(pom_path).map_err(|e| ResolveError::Io(e.to_string())));
 
-                        for repo in pom_data.repositories.drain(..) {
-                            let mut url = repo.trim().to_string();
-                            
-                            if !url.ends_with('/') {
-                                url.push('/');
-                            }
+                let variant = xml.as_ref().ok()
+                    .filter(|xml| xml.contains(GRADLE_METADATA_MARKER))
+                    .and_then(|_| self.module_variant(&art));
 
-                            if !self.repositories.contains(&url) {
-                                self.repositories.push(url);
-                            }
+                let mut constraints = Vec::new();
+
+                let pom = match variant {
+                    Some(variant) => {
+                        constraints = variant.constraints;
+
+                        if !variant.capabilities.is_empty() {
+                            graph.capabilities.insert(key.clone(), variant.capabilities);
                         }
 
-                        for dep in &mut pom_data.dependencies {
-                            dep.artifact.version = resolve_val(&dep.artifact.version, &pom_data.properties, &art);
+                        // Зависимостям без версии она достаётся от platform,
+                        // а если там её нет, то из dependencyManagement pom.
+                        // Репозитории тоже есть только в pom
+                        let pom = xml.and_then(|xml| self.get_effective_pom(&art, &xml, &[]))
+                            .unwrap_or_else(|e| {
+                                debug!("Cannot read pom of {}: {}", art, e);
+                                Pom::default()
+                            });
 
-                            if dep.artifact.version.is_empty() {
-                                if let Some(man) = pom_data.dep_management.iter().find(|m| {
-                                    m.artifact.group == dep.artifact.group && m.artifact.name == dep.artifact.name
-                                }) {
-                                    dep.artifact.version = resolve_val(&man.artifact.version, &pom_data.properties, &art);
-                                }
-                            }
+                        let mut dep_management = Vec::new();
+
+                        for platform in variant.platforms {
+                            debug!("Using versions from platform {}", platform);
+                            dep_management.extend(self.platform_versions(platform));
                         }
 
-                        info!(" Resolved {} ({} deps)", art, pom_data.dependencies.len());
+                        dep_management.extend(pom.dep_management);
 
-                        for dep in pom_data.dependencies {
-                            if dep.scope.as_ref().map_or(false, |s| s == "test" || s == "provided") {
-                                continue;
-                            }
+                        Ok(Pom {
+                            dependencies: variant.dependencies,
+                            dep_management,
+                            repositories: pom.repositories,
+                            packaging: Some(variant.extension),
+                            ..Default::default()
+                        })
+                    }
 
-                            let mut trans_art = dep.artifact;
+                    None => xml.and_then(|xml| self.get_effective_pom(&art, &xml, &[])),
+                };
 
-                            if trans_art.version.is_empty() {
-                                warn!("No version for dependency {}", trans_art);
-                                continue;
-                            }
+                let mut pom_data = match pom {
+                    Ok(pom) => {
+                        let packaging = pom.packaging.as_deref().unwrap_or("jar");
+                        self.packaging.insert(key.clone(), extension_for(packaging).to_string());
 
-                            if trans_art.is_dynamic() {
-                                match self.resolve_dynamic_version(&trans_art) {
-                                    Ok(v) => trans_art.version = v,
-                                    Err(e) => {
-                                        error!(
-                                            "Failed to resolve dynamic version for {}: {}",
-                                            trans_art,
-                                            e
-                                        );
-
-                                        continue;
-                                    }
-                                }
-                            }
+                        pom
+                    }
+
+                    Err(error) => {
+                        graph.failures.push(GraphFailure { owner: Some(art.clone()), artifact: art, error });
+                        continue;
+                    }
+                };
+
+                for repo in pom_data.repositories.drain(..) {
+                    add_repository(&mut self.repositories, apply_mirrors(&self.mirrors, Repository::new(&repo)));
+                }
+
+                // Переехавший артефакт своего файла не имеет, вместо него
+                // берётся тот на который он указывает
+                if let Some(relocation) = pom_data.relocation.take() {
+                    let mut target = Artifact::new(
+                        relocation.group_id.as_deref().unwrap_or(&art.group),
+                        relocation.artifact_id.as_deref().unwrap_or(&art.name),
+                        relocation.version.as_deref().unwrap_or(&art.version),
+                    );
+
+                    target.classifier = art.classifier.clone();
+                    target.extension = art.extension.clone();
+
+                    match &relocation.message {
+                        Some(message) => warn!("{} has been relocated to {}: {}", art, target, message),
+                        None => warn!("{} has been relocated to {}", art, target),
+                    }
+
+                    self.packaging.insert(key.clone(), "pom".to_string());
+
+                    pom_data.dependencies = vec![DependencyEntry {
+                        artifact: target,
+                        scope: None,
+                        entry_type: None,
+                        optional: false,
+                        exclusions: Vec::new(),
+                    }];
+                }
+
+                for dep in &mut pom_data.dependencies {
+                    let managed = pom_data.dep_management.iter().find(|m| m.manages(dep));
+
+                    // dependencyManagement дополняет то что не указано в самой зависимости
+                    if let Some(man) = managed {
+                        if dep.artifact.version.is_empty() {
+                            dep.artifact.version = man.artifact.version.clone();
+                        }
+
+                        if dep.scope.is_none() {
+                            dep.scope = man.scope.clone();
+                        }
+
+                        dep.exclusions.extend(man.exclusions.iter().cloned());
+                    }
+
+                    if let Some(entry_type) = &dep.entry_type {
+                        dep.artifact.set_type(entry_type);
+                    }
+                }
+
+                info!(" Resolved {} ({} deps)", art, pom_data.dependencies.len());
+
+                let mut edges = Vec::new();
+
+                for dep in pom_data.dependencies {
+                    // test и provided транзитивно не нужны никогда, system не поддерживается
+                    let scope = match Scope::parse(dep.scope.as_deref()) {
+                        Some(scope @ (Scope::Compile | Scope::Runtime)) => scope,
+                        _ => continue,
+                    };
+
+                    if dep.optional {
+                        debug!("Skipping optional dependency {} of {}", dep.artifact, art);
+                        continue;
+                    }
+
+                    let Some(mut trans_art) = self.substitute(dep.artifact) else {
+                        continue;
+                    };
+
+                    if trans_art.version.is_empty() {
+                        warn!("No version for dependency {}", trans_art);
+                        continue;
+                    }
+
+                    let range = match unresolved_property(&trans_art.to_string()) {
+                        Some(property) => Err(ResolveError::UnresolvedProperty { property }),
+                        None => self.select_version(&mut trans_art),
+                    };
 
-                            let v_id = format!("{}:{}", trans_art.id(), trans_art.version);
-                            if visited.insert(v_id) {
-                                next_queue.push(trans_art);
+                    match range {
+                        Ok(range) => {
+                            if !graph.edges.contains_key(&trans_art.to_string()) {
+                                next_queue.push(trans_art.clone());
                             }
+
+                            edges.push(Edge { artifact: trans_art, range, scope, exclusions: dep.exclusions });
                         }
+
+                        Err(error) => graph.failures.push(GraphFailure {
+                            owner: Some(art.clone()),
+                            artifact: trans_art,
+                            error,
+                        }),
+                    }
+                }
+
+                graph.edges.insert(key.clone(), edges);
+
+                // Сами constraints в граф ничего не добавляют, поэтому не обходятся
+                let mut published = Vec::new();
+
+                for constraint in constraints {
+                    let Some(mut target) = self.substitute(constraint.artifact) else {
+                        continue;
+                    };
+
+                    if target.version.is_empty() {
+                        continue;
+                    }
+
+                    match self.select_version(&mut target) {
+                        Ok(range) => published.push(Edge {
+                            artifact: target,
+                            range,
+                            scope: Scope::Compile,
+                            exclusions: Vec::new(),
+                        }),
+
+                        Err(error) => graph.failures.push(GraphFailure {
+                            owner: Some(art.clone()),
+                            artifact: target,
+                            error,
+                        }),
                     }
                 }
+
+                if !published.is_empty() {
+                    graph.published.insert(key, published);
+                }
             }
 
             queue = next_queue;
         }
+    }
 
-        self.resolve_version_conflicts(&all_versions);
+    /// Вариант из .module. None если .module не скачался, не разбирается
+    /// или в нём нет варианта для Android/JVM, тогда используется pom
+    fn module_variant(&self, art: &Artifact) -> Option<SelectedVariant> {
+        let json = self.fetch_artifact(art, "module")
+            .and_then(|path| fs::read_to_string(path).map_err(|e| ResolveError::Io(e.to_string())));
+
+        let module = match json {
+            Ok(json) => parse_module(&json),
+            Err(e) => {
+                debug!("No module metadata for {}: {}", art, e);
+                return None;
+            }
+        };
+
+        let module = match module {
+            Ok(module) => module,
+            Err(e) => {
+                warn!("Cannot parse {}, using pom: {}", art.get_path("module"), e);
+                return None;
+            }
+        };
+
+        match module.select() {
+            Some(variant) => {
+                debug!("Using variant {} of {}", variant.name, art);
+                Some(variant)
+            }
+
+            None => {
+                debug!("No Android or JVM variant in module metadata of {}, using pom", art);
+                None
+            }
+        }
     }
 
-    fn get_effective_pom(&self, art: &Artifact, xml: &str) -> Pom {
-        let mut pom = parse_pom(xml, art);
+    /// dependencyManagement платформы из .module. Если её не удалось
+    /// прочитать, то версий из неё просто нет
+    fn platform_versions(&self, mut platform: Artifact) -> Vec<DependencyEntry> {
+        if platform.version.is_empty() || self.select_version(&mut platform).is_err() {
+            return Vec::new();
+        }
 
-        process_imports(&mut pom.dep_management, self);
+        let pom = self.fetch_artifact(&platform, "pom")
+            .and_then(|path| fs::read_to_string(path).map_err(|e| ResolveError::Io(e.to_string())))
+            .and_then(|xml| self.get_effective_pom(&platform, &xml, &[]));
 
-        if let Some(parent_art) = pom.parent.clone() {
-            if let Ok(parent_path) = self.fetch_artifact(&parent_art, "pom") {
-                if let Ok(parent_xml) = fs::read_to_string(&parent_path) {
-                    let parent_pom = self.get_effective_pom(&parent_art, &parent_xml);
+        match pom {
+            Ok(pom) => pom.dep_management,
+            Err(e) => {
+                debug!("Cannot read platform {}: {}", platform, e);
+                Vec::new()
+            }
+        }
+    }
 
-                    for (k, v) in parent_pom.properties {
-                        pom.properties.entry(k).or_insert(v);
-                    }
+    /// Применяет [substitutions]. None если артефакт убран из графа
+    fn substitute(&self, art: Artifact) -> Option<Artifact> {
+        match self.substitutions.get(&art.module_id()) {
+            None => Some(art),
 
-                    let mut merged = pom.dep_management;
-                    merged.extend(parent_pom.dep_management);
-                    pom.dep_management = merged;
+            Some(None) => {
+                debug!("{} removed by substitution", art);
+                None
+            }
+
+            // Замена без версии берёт запрошенную
+            Some(Some(replacement)) => {
+                let mut replacement = replacement.clone();
 
-                    pom.repositories.extend(parent_pom.repositories);
+                if replacement.version.is_empty() {
+                    replacement.version = art.version.clone();
                 }
+
+                debug!("{} substituted with {}", art, replacement);
+                Some(replacement)
             }
         }
+    }
 
-        pom
+    /// Конкретная версия для объявленной. Версия из [constraints] важнее
+    /// всего, потом версия из spawn.lock. Для диапазона ([1.0,2.0), [1.6.1])
+    /// возвращает его самого: это жёсткое требование которое учитывается
+    /// при разрешении конфликтов. Просто версия (1.6.1) мягкая, её может
+    /// заменить другая
+    fn select_version(&self, art: &mut Artifact) -> Result<Option<VersionRange>, ResolveError> {
+        if let Some(v) = self.forced.get(&art.module_id()).or_else(|| self.pinned.get(&art.id())) {
+            art.version = v.clone();
+            return Ok(None);
+        }
+
+        if art.version == "LATEST" || art.version == "RELEASE" {
+            art.version = self.resolve_dynamic_version(art)?;
+        } else if art.is_dynamic() {
+            let range = VersionRange::parse(&art.version).map_err(|reason| ResolveError::InvalidVersion {
+                version: art.version.clone(),
+                reason,
+            })?;
+
+            art.version = match range.exact() {
+                Some(v) => v.to_string(),
+                None => {
+                    let constraint = self.constraints.get(&art.module_id());
+                    self.highest_version(art, |v| range.contains(v) && constraint.is_none_or(|c| c.contains(v)))?
+                }
+            };
+
+            return Ok(Some(range));
+        }
+
+        Ok(None)
     }
 
-    fn resolve_version_conflicts(&mut self, all_versions: &HashMap<String, Vec<Artifact>>) {
-        for (id, versions) in all_versions {
-            if versions.len() > 1 {
-                note!(" Conflict detected for {}: {} versions found", id, versions.len());
+    /// Переносит выбор версий в resolved_artifacts, объясняет каждый
+    /// конфликт и записывает проблемы которые остались в итоговом графе
+    fn apply_selection(
+        &mut self,
+        graph: &Graph,
+        selection: Selection,
+        unsatisfied: &[String],
+        capability_conflicts: &[CapabilityConflict],
+    ) {
+        self.resolved_artifacts = selection.selected.clone();
+
+        for (id, requests) in &selection.requests {
+            if !selection.is_conflict(id) {
+                continue;
             }
 
-            let winner = versions.iter().max_by(|a, b| {
-                let cmp_result = version_compare::compare(&a.version, &b.version);
-                match cmp_result {
-                    Ok(cmp) => match cmp {
-                        version_compare::Cmp::Gt | version_compare::Cmp::Ge => Ordering::Greater,
-                        version_compare::Cmp::Eq => Ordering::Equal,
-                        version_compare::Cmp::Lt | version_compare::Cmp::Le => Ordering::Less,
-                        version_compare::Cmp::Ne => Ordering::Equal,
-                    },
+            match selection.selected.get(id) {
+                _ if self.conflict_strategy == ConflictStrategy::Fail => note!(" Conflict for {}", id),
+                Some(winner) => note!(
+                    " Conflict for {}: selected {} ({})",
+                    id, winner.version, self.conflict_strategy
+                ),
+                None => note!(" Conflict for {}: no version selected", id),
+            }
 
-                    Err(_) => Ordering::Equal, // ошибка парсинга, можно считать равными
-                }
-            });
+            for request in requests {
+                info!("   {}", request);
+            }
+        }
 
-            if let Some(winner) = winner.cloned() {
-                self.resolved_artifacts.insert(id.clone(), winner.clone());
+        for failure in &graph.failures {
+            if let Some(path) = selection.failure_path(failure) {
+                self.fail(&failure.artifact, &path, failure.error.clone());
+            }
+        }
 
-                if versions.len() > 1 {
-                    info!(" Selected version {} for {}", winner.version, id);
-                }
+        // Мягкие версии в несовместимости диапазонов не виноваты
+        let explain = |id: &String, only_ranges: bool| -> (Artifact, Vec<Artifact>, Vec<String>) {
+            let requests = &selection.requests[id];
+            let first = &requests[0];
+
+            let mut lines: Vec<String> = requests.iter()
+                .filter(|r| !only_ranges || r.range.is_some())
+                .map(|r| r.to_string())
+                .collect();
+
+            if only_ranges && let Some(constraint) = graph.constraints.get(&first.artifact.module_id()) {
+                lines.push(format!("{} from [constraints]", constraint));
             }
+
+            (first.artifact.clone(), first.path.clone(), lines)
+        };
+
+        for id in unsatisfied {
+            let (art, path, requirements) = explain(id, true);
+            self.fail(&art, &path, ResolveError::VersionConflict { requirements });
+        }
+
+        for id in &selection.conflicts {
+            let (art, path, requests) = explain(id, false);
+            self.fail(&art, &path, ResolveError::ConflictingVersions { requests });
         }
+
+        for conflict in capability_conflicts {
+            let (art, _) = &conflict.providers[0];
+            let path = selection.paths.get(&art.to_string()).cloned().unwrap_or_default();
+            let providers = conflict.providers.iter().map(|(a, _)| a.to_string()).collect();
+
+            self.fail(art, &path, ResolveError::CapabilityConflict { capability: conflict.capability.clone(), providers });
+        }
+
+        self.dependency_paths = selection.paths;
+        self.scopes = selection.scopes;
+        self.processors = selection.processors;
+    }
+
+    fn fail(&mut self, art: &Artifact, path: &[Artifact], error: ResolveError) {
+        debug!("{}: {}", art, error);
+
+        self.unresolved.push(ResolveFailure {
+            artifact: art.clone(),
+            path: path.to_vec(),
+            error,
+        });
+    }
+
+    /// Цепочка зависимостей которая привела к артефакту. Для графа из
+    /// spawn.lock она неизвестна и пустая
+    fn dependency_path(&self, art: &Artifact) -> Vec<Artifact> {
+        self.dependency_paths.get(&art.to_string()).cloned().unwrap_or_default()
+    }
+
+    /// Pom после наследования от parent, подстановки свойств и импорта BOM.
+    /// chain это pom которые уже наследуются от этого или импортируют его,
+    /// по нему ловится цикл
+    fn get_effective_pom(&self, art: &Artifact, xml: &str, chain: &[Artifact]) -> Result<Pom, ResolveError> {
+        let mut pom = self.inherited_pom(art, xml, chain)?;
+        interpolate_pom(&mut pom, art)?;
+
+        let mut chain = chain.to_vec();
+        chain.push(art.clone());
+        process_imports(&mut pom.dep_management, self, &chain)?;
+
+        Ok(pom)
+    }
+
+    /// Pom вместе с активными профилями и тем что он наследует от parent.
+    /// Свойства ещё не подставлены: как и в Maven, они подставляются уже в
+    /// потомке
+    fn inherited_pom(&self, art: &Artifact, xml: &str, chain: &[Artifact]) -> Result<Pom, ResolveError> {
+        let mut pom = parse_pom(xml).map_err(|reason| ResolveError::Parse {
+            path: art.get_path("pom"),
+            reason,
+        })?;
+
+        for id in activate_profiles(&mut pom) {
+            debug!("Profile {} of {} is active", id, art);
+        }
+
+        if let Some(parent_art) = pom.parent.clone() {
+            let mut chain = chain.to_vec();
+            chain.push(art.clone());
+
+            if chain.contains(&parent_art) {
+                let names = chain.iter().chain([&parent_art]).map(|a| a.to_string()).collect();
+                return Err(ResolveError::Cycle(names));
+            }
+
+            let parent_path = self.fetch_artifact(&parent_art, "pom")?;
+            let parent_xml = fs::read_to_string(&parent_path).map_err(|e| ResolveError::Io(e.to_string()))?;
+            let parent_pom = self.inherited_pom(&parent_art, &parent_xml, &chain)?;
+
+            for (k, v) in parent_pom.properties {
+                pom.properties.entry(k).or_insert(v);
+            }
+
+            let mut merged = pom.dep_management;
+            merged.extend(parent_pom.dep_management);
+            pom.dep_management = merged;
+
+            pom.repositories.extend(parent_pom.repositories);
+
+            // groupId и version без parent не указывают
+            pom.group_id = pom.group_id.or(parent_pom.group_id);
+            pom.version = pom.version.or(parent_pom.version);
+        }
+
+        Ok(pom)
     }
 
-    pub fn download_all(&self) {
+    /// Скачивает файл каждого разрешённого артефакта, aar распаковывает.
+    /// Что не скачалось попадает в unresolved
+    pub fn download_all(&mut self) {
         task!("Download dependencies");
 
-        self.resolved_artifacts.par_iter().for_each(|(_, art)| {
-            let mut downloaded: Option<PathBuf> = None;
+        let failures: Vec<ResolveFailure> = self.resolved_artifacts.par_iter()
+            .filter_map(|(_, art)| {
+                // У артефакта без своего файла spawn.lock сверяет pom, при
+                // сборке по lock его в кэше ещё может не быть
+                if self.file_extensions(art).is_empty() {
+                    return self.fetch_artifact(art, "pom").err().map(|error| ResolveFailure {
+                        artifact: art.clone(),
+                        path: self.dependency_path(art),
+                        error,
+                    });
+                }
+
+                let mut error: Option<ResolveError> = None;
+
+                for ext in self.file_extensions(art) {
+                    let path = match self.fetch_artifact(art, ext) {
+                        Ok(path) => path,
 
-            if let Ok(path) = self.fetch_artifact(art, "aar") {
-                info!(" Aar: {}", art.name);
-                downloaded = Some(path);
+                        // Битый файл важнее того что следующий не нашёлся
+                        Err(e) => {
+                            if error.as_ref().is_none_or(|e| e.is_not_found()) {
+                                error = Some(e);
+                            }
+
+                            continue;
+                        }
+                    };
 
-                if let Err(e) = crate::resolver::unpacker::unpack_aar(downloaded.as_ref().unwrap()) {
-                    error!("unpack error {}: {}", art.name, e);
+                    match ext {
+                        "aar" => info!(" Aar: {}", art.name),
+                        "jar" => info!(" Jar: {}", art.name),
+                        other => info!(" {}: {}", other, art.name),
+                    }
+
+                    if ext != "aar" {
+                        return None;
+                    }
+
+                    return crate::resolver::unpacker::unpack_aar(&path).err().map(|e| ResolveFailure {
+                        artifact: art.clone(),
+                        path: self.dependency_path(art),
+                        error: ResolveError::Io(format!("unpack {}: {}", path.display(), e)),
+                    });
                 }
-            } else if let Ok(path) = self.fetch_artifact(art, "jar") {
-                info!(" Jar: {}", art.name);
-                downloaded = Some(path);
-            }
 
-            if downloaded.is_none() {
-                error!("{}", art);
+                error.map(|error| ResolveFailure {
+                    artifact: art.clone(),
+                    path: self.dependency_path(art),
+                    error,
+                })
+            })
+            .collect();
+
+        // Если не нашёлся pom, то и aar/jar скорее всего нет, второй раз это не показываем
+        for failure in failures {
+            if !self.unresolved.iter().any(|u| u.artifact == failure.artifact) {
+                self.unresolved.push(failure);
             }
-        });
+        }
     }
 
-    fn fetch_artifact(&self, art: &Artifact, ext: &str) -> Result<PathBuf, String> {
+    fn fetch_artifact(&self, art: &Artifact, ext: &str) -> Result<PathBuf, ResolveError> {
         let mut rel_path = art.get_path(ext);
-        let file_name;
 
         if art.is_snapshot() {
-            let snapshot_version = self.resolve_snapshot(art, ext)?;
-            file_name = format!("{}-{}.{}", art.name, snapshot_version, ext);
+            let snapshot_version = match self.pinned_snapshots.get(&art.to_string()) {
+                Some(v) => Some(v.clone()),
+                None => match self.resolve_snapshot(art, ext) {
+                    Ok(v) => Some(v),
+                    Err(_) if self.offline => Some(self.cached_snapshot(art, ext)?),
+
+                    // В ~/.m2 (там maven-metadata-local.xml) и в репозиториях без
+                    // уникальных snapshot файл лежит как name-1.0-SNAPSHOT.ext
+                    Err(e) => {
+                        debug!("{}, trying {}", e, rel_path);
+                        None
+                    }
+                },
+            };
 
-            let g = art.group.replace('.', "/");
-            rel_path = format!("{}/{}/{}/{}", g, art.name, art.version, file_name);
+            if let Some(snapshot_version) = snapshot_version {
+                let g = art.group.replace('.', "/");
+                rel_path = format!("{}/{}/{}/{}", g, art.name, art.version, art.file_name(&snapshot_version, ext));
+            }
         }
 
         let full_path = self.cache_root.join(&rel_path);
 
-        if full_path.exists() {
+        if self.is_cached(&full_path, &rel_path) {
+            return Ok(full_path);
+        }
+
+        if self.offline {
+            return Err(ResolveError::NotCached { path: rel_path });
+        }
+
+        // Пока файл качается другим процессом spawn мы ждём, а потом берём готовый
+        let _lock = download::lock(&full_path).map_err(|e| ResolveError::Io(e.to_string()))?;
+
+        if self.is_cached(&full_path, &rel_path) {
             return Ok(full_path);
         }
 
-        for repo in &self.repositories {
-            let url = format!("{}{}", repo, rel_path);
+        let mut last_error = None;
 
-            if let Ok(resp) = self.client.get(&url).send() {
-                if resp.status().is_success() {
-                    fs::create_dir_all(full_path.parent().unwrap()).ok();
+        let mut repos = self.repositories_for(&art.group);
 
-                    let bytes = resp.bytes().map_err(|e| e.to_string())?;
-                    let mut out = fs::File::create(&full_path).map_err(|e| e.to_string())?;
-                    out.write_all(&bytes).map_err(|e| e.to_string())?;
+        if let Some(source) = self.pinned_sources.get(&source_key(art)) {
+            repos.sort_by_key(|r| &r.url != source);
+        }
+
+        for repo in repos {
+            let url = format!("{}{}", repo.url, rel_path);
+            debug!("GET {}", url);
+
+            let part = match download::download(&self.http, repo, &url, &full_path) {
+                Ok(Some(part)) => part,
+                Ok(None) => continue,
+                Err(e) => {
+                    // Недокачанный .part остаётся и докачивается при следующей попытке.
+                    // Про недоступный репозиторий Http уже предупредил, он попадёт в not_found
+                    if !self.http.is_down(repo) {
+                        warn!("{}", e);
+                        last_error = Some(ResolveError::Network(e));
+                    }
+
+                    continue;
+                }
+            };
 
-                    return Ok(full_path);
+            // Файл попадает в кэш только после проверки контрольной суммы,
+            // иначе пробуем следующий репозиторий
+            let sha256 = match self.verify_checksum(repo, &url, &part) {
+                Ok(hash) => hash,
+                Err(e) => {
+                    warn!("{}", e);
+                    fs::remove_file(&part).ok();
+                    last_error = Some(e);
+                    continue;
                 }
+            };
+
+            // Запоминаем откуда пришёл файл и его проверенный хэш, это попадает в spawn.lock.
+            // Sidecar файлы пишутся раньше самого файла, чтобы его наличие означало что они тоже есть
+            download::write_atomic(&sidecar_path(&full_path, "source"), &repo.url).ok();
+            download::write_atomic(&sidecar_path(&full_path, Algorithm::Sha256.ext()), sha256).ok();
+
+            download::commit(&part, &full_path).map_err(|e| ResolveError::Io(e.to_string()))?;
+
+            return Ok(full_path);
+        }
+
+        Err(last_error.unwrap_or_else(|| self.not_found(&art.group, &rel_path)))
+    }
+
+    /// Есть ли актуальный файл в кэше. Файл скопированный из file://
+    /// репозитория устаревает когда оригинал пересобирают (mvn install),
+    /// тогда он копируется заново
+    fn is_cached(&self, full_path: &Path, rel_path: &str) -> bool {
+        let Ok(cached) = fs::metadata(full_path).and_then(|m| m.modified()) else {
+            return false;
+        };
+
+        let origin = self.artifact_source(full_path)
+            .and_then(|url| file_url_path(&url))
+            .and_then(|dir| fs::metadata(dir.join(rel_path)).and_then(|m| m.modified()).ok());
+
+        match origin {
+            Some(modified) if modified > cached => {
+                debug!("{} changed in local repository", rel_path);
+                false
             }
+
+            _ => true,
+        }
+    }
+
+    /// Может ли репозиторий отдавать группу. Если какой-то репозиторий
+    /// объявил группу своей через include, то она ищется только в таких
+    /// репозиториях, это защищает внутренние группы от подмены в публичных
+    fn serves(&self, repo: &Repository, group: &str) -> bool {
+        if repo.excludes(group) {
+            return false;
+        }
+
+        if self.repositories.iter().any(|r| r.includes(group)) {
+            repo.includes(group)
+        } else {
+            repo.include.is_empty()
+        }
+    }
+
+    /// Репозитории для группы, кроме помеченных недоступными. После подмены
+    /// зеркалом несколько записей могут указывать на один URL, он опрашивается один раз
+    fn repositories_for(&self, group: &str) -> Vec<&Repository> {
+        let mut seen = HashSet::new();
+
+        self.repositories.iter()
+            .filter(|r| self.serves(r, group) && !self.http.is_down(r))
+            .filter(|r| seen.insert(r.url.as_str()))
+            .collect()
+    }
+
+    /// Файла нет ни в одном доступном репозитории. Если часть репозиториев
+    /// недоступна, то это тоже попадает в сообщение, ведь файл мог быть там
+    fn not_found(&self, group: &str, rel_path: &str) -> ResolveError {
+        if !self.repositories.iter().any(|r| self.serves(r, group)) {
+            return ResolveError::NoRepository {
+                group: group.to_string(),
+                path: rel_path.to_string(),
+            };
+        }
+
+        ResolveError::NotFound {
+            path: rel_path.to_string(),
+            unavailable: self.http.down_repositories(),
+        }
+    }
+
+    /// Сверяет скачанный файл с контрольной суммой которую публикует репозиторий
+    /// (url.sha512, url.sha256 или url.sha1). Возвращает SHA-256 файла
+    fn verify_checksum(&self, repo: &Repository, url: &str, path: &Path) -> Result<String, ResolveError> {
+        let io_error = |e: io::Error| ResolveError::Io(e.to_string());
+        let sha256 = Algorithm::Sha256.digest_file(path).map_err(io_error)?;
+
+        for algorithm in ALGORITHMS {
+            let Some(expected) = self.fetch_checksum(repo, url, algorithm).map_err(ResolveError::Network)? else {
+                continue;
+            };
+
+            let actual = match algorithm {
+                Algorithm::Sha256 => sha256.clone(),
+                _ => algorithm.digest_file(path).map_err(io_error)?,
+            };
+
+            if actual != expected {
+                return Err(ResolveError::ChecksumMismatch {
+                    url: url.to_string(),
+                    algorithm: algorithm.ext().to_string(),
+                    expected,
+                    actual,
+                });
+            }
+
+            debug!("Verified {} ({})", url, algorithm.ext());
+            return Ok(sha256);
+        }
+
+        // mvn install и сборки в папку не пишут контрольных сумм, а файлу
+        // на своём диске и так можно доверять
+        if repo.local_path().is_some() {
+            debug!("No checksum for local file {}", url);
+            return Ok(sha256);
+        }
+
+        match self.checksum_policy {
+            ChecksumPolicy::Lenient => {
+                warn!("No checksum published for {}, accepted in lenient mode", url);
+                Ok(sha256)
+            }
+
+            ChecksumPolicy::Strict => Err(ResolveError::MissingChecksum { url: url.to_string() }),
         }
+    }
+
+    fn fetch_checksum(&self, repo: &Repository, url: &str, algorithm: Algorithm) -> Result<Option<String>, String> {
+        let checksum_url = format!("{}.{}", url, algorithm.ext());
+        debug!("GET {}", checksum_url);
 
-        Err(format!("{} Not found: {}", "ERROR".red(), rel_path))
+        let Some(bytes) = self.http.fetch(repo, &checksum_url)? else {
+            return Ok(None);
+        };
+
+        Ok(parse_checksum(&String::from_utf8_lossy(&bytes), algorithm))
     }
 
-    fn fetch_metadata(&self, art: &Artifact, per_version: bool) -> Result<String, String> {
+    fn fetch_metadata(&self, art: &Artifact, per_version: bool) -> Result<String, ResolveError> {
         let rel_path = art.get_metadata_path(per_version);
         let full_path = self.cache_root.join(&rel_path);
 
@@ -296,12 +1029,11 @@ impl Resolver {
         if full_path.exists() {
             if let Ok(metadata) = fs::metadata(&full_path) {
                 // maven-metadata.xml обычно > 256 байт
-                if metadata.len() >= 256 {
-                    if let Ok(content) = fs::read_to_string(&full_path) {
-                        if !content.trim().is_empty() {
-                            return Ok(content);
-                        }
-                    }
+                if metadata.len() >= 256
+                    && let Ok(content) = fs::read_to_string(&full_path)
+                    && !content.trim().is_empty()
+                {
+                    return Ok(content);
                 }
             }
 
@@ -310,51 +1042,45 @@ impl Resolver {
             let _ = fs::remove_file(&full_path);
         }
 
+        if self.offline {
+            return Err(ResolveError::NotCached { path: rel_path });
+        }
+
         // Скачивание из репозиториев
-        for repo in &self.repositories {
-            let url = format!("{}{}", repo, rel_path);
-
-            match self.client.get(&url).send() {
-                Ok(resp) if resp.status().is_success() => {
-                    match resp.text() {
-                        Ok(text) => {
-                            if text.trim().is_empty() {
-                                // Если ответ пустой, то пробуем следующий репозиторий
-                                continue;
-                            }
+        for repo in self.repositories_for(&art.group) {
+            let url = format!("{}{}", repo.url, rel_path);
+            debug!("GET {}", url);
 
-                            // Сохранение в кэш
-                            if let Some(parent) = full_path.parent() {
-                                let _ = fs::create_dir_all(parent);
-                            }
+            match self.http.fetch(repo, &url) {
+                Ok(Some(bytes)) => {
+                    let text = String::from_utf8_lossy(&bytes).into_owned();
 
-                            if let Ok(mut file) = fs::File::create(&full_path) {
-                                let _ = file.write_all(text.as_bytes());
-                            }
+                    if text.trim().is_empty() {
+                        // Если ответ пустой, то пробуем следующий репозиторий
+                        continue;
+                    }
 
-                            return Ok(text);
-                        }
+                    // Сохранение в кэш. Те же метаданные может писать другой
+                    // поток или процесс spawn
+                    let _lock = download::lock(&full_path);
+                    let _ = download::write_atomic(&full_path, &text);
 
-                        Err(e) => return Err(format!("Failed to read response text: {}", e)),
-                    }
-                }
-                Ok(_resp) => {
-                    // Если 200 просто идём дальше
-                    continue;
-                }
-                Err(e) => {
-                    // Сетевая ошибка, можно логировать, но тут идёт попытка использовать другой
-                    // репозиторий для скачивания (continue переходит к другому репо)
-                    warn!("Network error fetching metadata {}: {}", url, e);
-                    continue;
+                    return Ok(text);
                 }
+
+                // Метаданных в этом репозитории нет, идём дальше
+                Ok(None) => continue,
+
+                // Репозиторий не ответил после всех попыток и уже помечен
+                // недоступным, пробуем другой
+                Err(_) => continue,
             }
         }
 
-        Err(format!("Metadata not found for {} at path {}", art, rel_path))
+        Err(self.not_found(&art.group, &rel_path))
     }
 
-    fn resolve_snapshot(&self, art: &Artifact, ext: &str) -> Result<String, String> {
+    fn resolve_snapshot(&self, art: &Artifact, ext: &str) -> Result<String, ResolveError> {
         let xml = self.fetch_metadata(art, true)?;
 
         let mut reader = Reader::from_str(&xml);
@@ -363,6 +1089,7 @@ impl Resolver {
         let mut buf = Vec::new();
         let mut path = Vec::new();
         let mut cur_ext = String::new();
+        let mut cur_classifier = String::new();
         let mut cur_val = String::new();
         let mut latest_val = String::new();
 
@@ -376,6 +1103,8 @@ impl Resolver {
                     
                     if full_path == "metadata/versioning/snapshotVersions/snapshotVersion/extension" {
                         cur_ext = val;
+                    } else if full_path == "metadata/versioning/snapshotVersions/snapshotVersion/classifier" {
+                        cur_classifier = val;
                     } else if full_path == "metadata/versioning/snapshotVersions/snapshotVersion/value" {
                         cur_val = val;
                     }
@@ -384,11 +1113,12 @@ impl Resolver {
                 Ok(Event::End(_)) => {
                     let full_path = path.join("/");
                     if full_path == "metadata/versioning/snapshotVersions/snapshotVersion" {
-                        if cur_ext == ext {
+                        if cur_ext == ext && cur_classifier == art.file_classifier(ext).unwrap_or_default() {
                             latest_val = cur_val.clone();
                         }
 
                         cur_ext.clear();
+                        cur_classifier.clear();
                         cur_val.clear();
                     }
 
@@ -396,7 +1126,12 @@ impl Resolver {
                 }
 
                 Ok(Event::Eof) => break,
-                
+
+                Err(e) => return Err(ResolveError::Parse {
+                    path: art.get_metadata_path(true),
+                    reason: e.to_string(),
+                }),
+
                 _ => (),
             }
 
@@ -404,13 +1139,51 @@ impl Resolver {
         }
 
         if latest_val.is_empty() {
-            Err(format!("No snapshot version for ext {} in {}", ext, art))
+            Err(ResolveError::NotFound {
+                path: format!("{} (snapshot .{})", art.get_metadata_path(true), ext),
+                unavailable: Vec::new(),
+            })
         } else {
             Ok(latest_val)
         }
     }
 
-    fn resolve_dynamic_version(&self, art: &Artifact) -> Result<String, String> {
+    /// Ищет в кэше файл snapshot артефакта: либо name-1.0-SNAPSHOT.ext, либо с
+    /// временной меткой name-1.0-20240101.120000-1.ext. Берётся самый свежий
+    /// по метке и номеру сборки, файл без метки только если других нет
+    fn cached_snapshot(&self, art: &Artifact, ext: &str) -> Result<String, ResolveError> {
+        let g = art.group.replace('.', "/");
+        let dir = self.cache_root.join(&g).join(&art.name).join(&art.version);
+        let not_cached = || ResolveError::NotCached { path: art.get_path(ext) };
+
+        let prefix = format!("{}-", art.name);
+        let suffix = match art.file_classifier(ext) {
+            Some(classifier) => format!("-{}.{}", classifier, ext),
+            None => format!(".{}", ext),
+        };
+
+        fs::read_dir(&dir)
+            .map_err(|_| not_cached())?
+            .filter_map(|e| e.ok())
+            .filter_map(|e| e.file_name().into_string().ok())
+            .filter_map(|n| {
+                let version = n.strip_prefix(&prefix)?.strip_suffix(&suffix)?;
+                let build = snapshot_build(&art.version, version)?;
+
+                Some((build, version.to_string()))
+            })
+            .max()
+            .map(|(_, version)| version)
+            .ok_or_else(not_cached)
+    }
+
+    /// Опубликованные версии артефакта из maven-metadata.xml. В offline
+    /// режиме это версии которые уже есть в кэше
+    fn available_versions(&self, art: &Artifact) -> Result<VersionMetadata, ResolveError> {
+        if self.offline {
+            return self.cached_versions(art);
+        }
+
         let xml = self.fetch_metadata(art, false)?;
 
         let mut reader = Reader::from_str(&xml);
@@ -443,124 +1216,470 @@ impl Resolver {
                 }
                 
                 Ok(Event::Eof) => break,
-                
+
+                Err(e) => return Err(ResolveError::Parse {
+                    path: art.get_metadata_path(false),
+                    reason: e.to_string(),
+                }),
+
                 _ => (),
             }
 
             buf.clear();
         }
 
-        match art.version.as_str() {
-            "LATEST" => if !latest.is_empty() {
-                Ok(latest)
-            } else {
-                Err("No latest version".to_string())
-            },
-
-            "RELEASE" => if !release.is_empty() {
-                Ok(release)
-            } else {
-                Err("No release version".to_string())
-            },
-
-            _ => {
-                let range = &art.version;
-                let mut matching: Vec<String> = versions
-                    .into_iter()
-                    .filter(|v| matches_range(v, range))
-                    .collect();
-                
-                matching.sort_by(|a, b| {
-                    let cmp_result = version_compare::compare(a, b);
-                    match cmp_result {
-                        Ok(cmp) => match cmp {
-                            version_compare::Cmp::Gt | version_compare::Cmp::Ge => Ordering::Greater,
-                            version_compare::Cmp::Eq => Ordering::Equal,
-                            version_compare::Cmp::Lt | version_compare::Cmp::Le => Ordering::Less,
-                            version_compare::Cmp::Ne => Ordering::Equal,
-                        },
-                        Err(_) => Ordering::Equal,
-                    }
-                });
+        Ok(VersionMetadata { latest, release, versions })
+    }
 
-                matching
-                    .last()
-                    .cloned()
-                    .ok_or_else(|| "No matching version for range".to_string())
-            }
+    fn cached_versions(&self, art: &Artifact) -> Result<VersionMetadata, ResolveError> {
+        let g = art.group.replace('.', "/");
+        let dir = self.cache_root.join(&g).join(&art.name);
+
+        let versions: Vec<String> = fs::read_dir(&dir)
+            .map_err(|_| ResolveError::NotCached { path: format!("{}/{}/{}", g, art.name, art.version) })?
+            .filter_map(|e| e.ok())
+            .filter(|e| e.path().is_dir())
+            .filter_map(|e| e.file_name().into_string().ok())
+            .collect();
+
+        let highest = |snapshots: bool| versions.iter()
+            .filter(|v| snapshots || !v.ends_with("-SNAPSHOT"))
+            .max_by(|a, b| compare_versions(a, b))
+            .cloned()
+            .unwrap_or_default();
+
+        Ok(VersionMetadata { latest: highest(true), release: highest(false), versions })
+    }
+
+    /// Самая старшая опубликованная версия для которой allowed вернул true
+    fn highest_version(&self, art: &Artifact, allowed: impl Fn(&str) -> bool) -> Result<String, ResolveError> {
+        self.available_versions(art)?
+            .versions
+            .into_iter()
+            .filter(|v| allowed(v))
+            .max_by(|a, b| compare_versions(a, b))
+            .ok_or_else(|| ResolveError::NoMatchingVersion { range: art.version.clone() })
+    }
+
+    /// LATEST или RELEASE
+    fn resolve_dynamic_version(&self, art: &Artifact) -> Result<String, ResolveError> {
+        let metadata = self.available_versions(art)?;
+
+        let version = if art.version == "LATEST" { metadata.latest } else { metadata.release };
+
+        if version.is_empty() {
+            Err(ResolveError::NoMatchingVersion { range: art.version.clone() })
+        } else {
+            Ok(version)
         }
     }
 
     pub fn verify_all_artifacts_exist(&self) -> bool {
-        for (_, artifact) in &self.resolved_artifacts {
-            let aar_path = self.cache_root.join(artifact.get_path("aar"));
-            let jar_path = self.cache_root.join(artifact.get_path("jar"));
+        let missing = self.missing_artifacts();
 
-            let aar_ok = aar_path.exists() && fs::metadata(&aar_path)
-                .map(|m| m.len() >= 1024)
-                .unwrap_or(false);
+        for artifact in &missing {
+            warn!("missing {}", artifact);
+        }
+
+        missing.is_empty()
+    }
+
+    /// Разрешённые артефакты у которых есть свой файл, но его нет в кэше
+    pub fn missing_artifacts(&self) -> Vec<&Artifact> {
+        self.resolved_artifacts.values()
+            .filter(|art| !self.file_extensions(art).is_empty() && self.artifact_file(art).is_none())
+            .collect()
+    }
 
-            let jar_ok = jar_path.exists() && fs::metadata(&jar_path)
+    /// Путь к скачанному файлу артефакта и его расширение
+    pub fn artifact_file(&self, art: &Artifact) -> Option<(PathBuf, String)> {
+        self.file_extensions(art).into_iter().find_map(|ext| {
+            let path = self.cached_path(art, ext)?;
+
+            let ok = fs::metadata(&path)
                 .map(|m| m.len() >= 1024)
                 .unwrap_or(false);
 
-            if !aar_ok && !jar_ok {
-                warn!("missing {}", artifact);
-                return false;
-            }
+            ok.then(|| (path, ext.to_string()))
+        })
+    }
+
+    /// Файл который фиксируется в spawn.lock: aar/jar, а у артефакта без
+    /// своего файла его pom
+    pub fn locked_file(&self, art: &Artifact) -> Option<(PathBuf, String)> {
+        if !self.file_extensions(art).is_empty() {
+            return self.artifact_file(art);
         }
-        
-        true
+
+        let path = self.cached_path(art, "pom")?;
+        path.exists().then(|| (path, "pom".to_string()))
     }
-}
 
-fn matches_range(version: &str, range_str: &str) -> bool {
-    let trimmed = range_str.trim();
+    /// Какие файлы искать у артефакта, в порядке предпочтения. Пусто если
+    /// своего файла у него нет (packaging pom, Kotlin Multiplatform корень)
+    fn file_extensions<'a>(&'a self, art: &'a Artifact) -> Vec<&'a str> {
+        let packaging = art.extension.as_deref()
+            .or_else(|| self.packaging.get(&art.to_string()).map(String::as_str));
+
+        // <packaging> у старых библиотек бывает неверным, поэтому если
+        // файла нет, пробуется и второй вариант. Явно заданное расширение
+        // не перепроверяется
+        match packaging {
+            Some("pom") => Vec::new(),
+            Some(ext) if art.extension.is_some() => vec![ext],
+            Some("jar") => vec!["jar", "aar"],
+            Some("aar") | None => vec!["aar", "jar"],
+            Some(ext) => vec![ext],
+        }
+    }
+
+    /// Путь в кэше без обращения к сети. Для snapshot берётся версия из
+    /// spawn.lock либо самая свежая из скачанных
+    fn cached_path(&self, art: &Artifact, ext: &str) -> Option<PathBuf> {
+        if !art.is_snapshot() {
+            return Some(self.cache_root.join(art.get_path(ext)));
+        }
+
+        let snapshot_version = self.snapshot_version(art, ext)?;
+        let g = art.group.replace('.', "/");
 
-    if !trimmed.contains(',') && !trimmed.contains('(') && !trimmed.contains('[') {
-        return version_compare::compare(version, trimmed)
-            .map(|c| c == version_compare::Cmp::Eq)
-            .unwrap_or(false);
+        Some(self.cache_root
+            .join(g)
+            .join(&art.name)
+            .join(&art.version)
+            .join(art.file_name(&snapshot_version, ext)))
     }
 
-    let (lower, upper) = if trimmed.contains(',') {
-        let parts: Vec<&str> = trimmed.splitn(2, ',').collect();
-        (parts[0], parts.get(1).copied().unwrap_or(""))
-    } else {
-        (trimmed, "")
-    };
+    /// Точная версия файла snapshot артефакта (с временной меткой)
+    pub fn snapshot_version(&self, art: &Artifact, ext: &str) -> Option<String> {
+        self.pinned_snapshots.get(&art.to_string())
+            .cloned()
+            .or_else(|| self.cached_snapshot(art, ext).ok())
+    }
 
-    let lower_incl = lower.starts_with('[');
-    let lower_bound = lower.trim_start_matches(|c| c == '(' || c == '[');
+    /// Репозиторий из которого был скачан файл, если это известно
+    pub fn artifact_source(&self, path: &Path) -> Option<String> {
+        fs::read_to_string(sidecar_path(path, "source"))
+            .ok()
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+    }
 
-    let upper_incl = upper.ends_with(']');
-    let upper_bound = upper.trim_end_matches(|c| c == ')' || c == ']');
+    /// SHA-256 файла проверенный при скачивании. Для файлов скачанных старыми
+    /// версиями spawn записи нет, тогда хэш считается по файлу в кэше
+    pub fn artifact_sha256(&self, path: &Path) -> Option<String> {
+        fs::read_to_string(sidecar_path(path, Algorithm::Sha256.ext()))
+            .ok()
+            .and_then(|s| parse_checksum(&s, Algorithm::Sha256))
+            .or_else(|| sha256_file(path).ok())
+    }
 
-    let mut ok = true;
+    /// Проверяет PGP подписи pom и aar/jar всех разрешённых артефактов.
+    /// Вызывать после download_all. Подписи скачиваются в кэш рядом с файлами
+    /// (foo.jar.asc), в offline режиме используются только уже скачанные
+    pub fn verify_signatures(&self, verifier: &SignatureVerifier) -> SignatureReport {
+        task!("Verifying signatures");
+
+        let mut entries: Vec<SignatureEntry> = self.resolved_artifacts.values()
+            .flat_map(|art| {
+                let pom = self.cached_path(art, "pom");
+                let file = self.artifact_file(art).map(|(path, _)| path);
+
+                [pom, file].into_iter()
+                    .flatten()
+                    .filter(|path| path.exists())
+                    .map(move |path| (art, path))
+            })
+            .map(|(art, path)| {
+                let signature = self.fetch_signature(art, &path);
+                let status = verifier.verify(art, &path, &signature);
+
+                debug!("{}: {}", path.display(), status);
+
+                SignatureEntry {
+                    artifact: art.clone(),
+                    file: path,
+                    status,
+                }
+            })
+            .collect();
 
-    if !lower_bound.is_empty() {
-        if let Ok(cmp) = version_compare::compare(version, lower_bound) {
-            ok &= match cmp {
-                version_compare::Cmp::Gt | version_compare::Cmp::Ge => !lower_incl,
-                version_compare::Cmp::Eq => lower_incl,
-                _ => false,
-            };
-        } else {
-            ok = false;
+        entries.sort_by(|a, b| a.file.cmp(&b.file));
+
+        SignatureReport { entries }
+    }
+
+    /// Путь к .asc рядом с файлом в кэше. Если подписи ещё нет, то она
+    /// скачивается сначала из репозитория откуда пришёл файл, потом из остальных
+    fn fetch_signature(&self, art: &Artifact, path: &Path) -> PathBuf {
+        let signature = sidecar_path(path, "asc");
+
+        if signature.exists() || self.offline {
+            return signature;
+        }
+
+        let Ok(rel_path) = path.strip_prefix(&self.cache_root) else {
+            return signature;
+        };
+
+        let rel_path = rel_path.to_string_lossy().replace('\\', "/");
+        let source = self.artifact_source(path);
+
+        // Сначала репозиторий из которого пришёл сам файл
+        let mut repos = self.repositories_for(&art.group);
+        repos.sort_by_key(|r| Some(&r.url) != source.as_ref());
+
+        for repo in repos {
+            let url = format!("{}{}.asc", repo.url, rel_path);
+            debug!("GET {}", url);
+
+            if let Ok(Some(bytes)) = self.http.fetch(repo, &url) {
+                download::write_atomic(&signature, &bytes).ok();
+                break;
+            }
+        }
+
+        signature
+    }
+
+    /// Запоминает из каких репозиториев пришли файлы по spawn.lock.
+    /// Используется и при частичном обновлении для зафиксированных версий
+    pub fn prefer_sources(&mut self, artifacts: &[LockedArtifact]) {
+        for locked in artifacts {
+            if let Some(repository) = &locked.repository {
+                self.pinned_sources.insert(source_key(&locked.artifact()), repository.clone());
+            }
+        }
+    }
+
+    /// Заполняет граф из spawn.lock вместо разрешения зависимостей
+    pub fn load_locked(&mut self, artifacts: &[LockedArtifact]) {
+        self.prefer_sources(artifacts);
+
+        for locked in artifacts {
+            let art = locked.artifact();
+
+            if let Some(snapshot) = &locked.snapshot {
+                self.pinned_snapshots.insert(art.to_string(), snapshot.clone());
+            }
+
+            self.scopes.insert(locked.id(), locked.scope);
+            self.packaging.insert(art.to_string(), locked.packaging.clone());
+
+            if locked.processor || locked.scope == Scope::Processor {
+                self.processors.insert(locked.id());
+            }
+
+            self.resolved_artifacts.insert(locked.id(), art);
         }
     }
 
-    if !upper_bound.is_empty() {
-        if let Ok(cmp) = version_compare::compare(version, upper_bound) {
-            ok &= match cmp {
-                version_compare::Cmp::Lt | version_compare::Cmp::Le => !upper_incl,
-                version_compare::Cmp::Eq => upper_incl,
-                _ => false,
+    pub fn scope(&self, art: &Artifact) -> Scope {
+        self.scopes.get(&art.id()).copied().unwrap_or_default()
+    }
+
+    /// Сверяет SHA-256 скачанных файлов с записанными в spawn.lock. Возвращает
+    /// список несовпадений
+    pub fn verify_locked(&self, artifacts: &[LockedArtifact]) -> Vec<String> {
+        let mut errors = Vec::new();
+
+        for locked in artifacts {
+            let art = locked.artifact();
+
+            let Some(path) = self.cached_path(&art, &locked.packaging) else {
+                errors.push(format!("{} is missing", art));
+                continue;
             };
-        } else {
-            ok = false;
+
+            match sha256_file(&path) {
+                Ok(hash) if hash == locked.sha256 => {}
+                Ok(hash) => errors.push(format!(
+                    "{} checksum mismatch: expected {}, got {}",
+                    art,
+                    locked.sha256,
+                    hash
+                )),
+                Err(e) => errors.push(format!("{} is missing: {}", art, e)),
+            }
         }
+
+        errors
     }
+}
 
-    ok
-}
\ No newline at end of file
+pub fn sha256_file(path: &Path) -> io::Result<String> {
+    Algorithm::Sha256.digest_file(path)
+}
+
+/// Добавляет репозиторий из pom в конец списка если такого URL там ещё нет
+fn add_repository(repositories: &mut Vec<Repository>, repo: Repository) {
+    if !repositories.iter().any(|r| r.url == repo.url) {
+        repositories.push(repo);
+    }
+}
+
+/// Имя свойства если в значении остался ${...} который нечем заменить
+fn unresolved_property(value: &str) -> Option<String> {
+    let start = value.find("${")? + 2;
+    let len = value[start..].find('}')?;
+
+    Some(value[start..start + len].to_string())
+}
+
+/// Ключ pinned_sources. Расширения в нём нет, pom и сам файл артефакта
+/// берутся из одного репозитория
+fn source_key(art: &Artifact) -> String {
+    format!("{}:{}", art.id(), art.version)
+}
+
+/// Файл рядом с артефактом (foo.jar.source, foo.jar.sha256)
+fn sidecar_path(path: &Path, ext: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".");
+    name.push(ext);
+    PathBuf::from(name)
+}
+
+/// Метка и номер сборки snapshot файла: (20240101.120000, 3) для
+/// 1.0-20240101.120000-3, пустая метка для самого 1.0-SNAPSHOT. Всё
+/// остальное (например 1.0-SNAPSHOT-sources) это чужой файл
+fn snapshot_build(version: &str, candidate: &str) -> Option<(String, u32)> {
+    if candidate == version {
+        return Some((String::new(), 0));
+    }
+
+    let base = version.strip_suffix("-SNAPSHOT")?;
+    let (timestamp, build) = candidate.strip_prefix(base)?.strip_prefix('-')?.split_once('-')?;
+
+    let (date, time) = timestamp.split_once('.')?;
+    let digits = |s: &str, len: usize| s.len() == len && s.bytes().all(|b| b.is_ascii_digit());
+
+    if !digits(date, 8) || !digits(time, 6) || !build.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+
+    Some((timestamp.to_string(), build.parse().ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_pom(repo: &Path, group: &str, name: &str, version: &str, body: &str) {
+        let dir = repo.join(group.replace('.', "/")).join(name).join(version);
+        fs::create_dir_all(&dir).unwrap();
+
+        let xml = format!(
+            "<project><modelVersion>4.0.0</modelVersion><groupId>{}</groupId>\
+             <artifactId>{}</artifactId><version>{}</version>{}</project>",
+            group, name, version, body
+        );
+
+        fs::write(dir.join(format!("{}-{}.pom", name, version)), xml).unwrap();
+    }
+
+    #[test]
+    fn follows_relocation() {
+        let base = std::env::temp_dir().join(format!("spawn-test-relocation-{}", std::process::id()));
+        let repo = base.join("repo");
+        fs::remove_dir_all(&base).ok();
+
+        write_pom(&repo, "org.old", "lib", "1.0", "<distributionManagement><relocation>\
+            <groupId>org.new</groupId><artifactId>lib2</artifactId>\
+            </relocation></distributionManagement>");
+
+        write_pom(&repo, "org.new", "lib2", "1.0", "<dependencies><dependency>\
+            <groupId>org.new</groupId><artifactId>util</artifactId><version>2.0</version>\
+            </dependency></dependencies>");
+
+        write_pom(&repo, "org.new", "util", "2.0", "");
+
+        let mut resolver = Resolver::new(vec![Repository::local("test", &repo)], &base);
+        resolver.resolve(vec!["org.old:lib:1.0".to_string()]);
+
+        let result = (
+            resolver.unresolved.is_empty(),
+            resolver.resolved_artifacts.get("org.new:lib2").map(|a| a.version.clone()),
+            resolver.resolved_artifacts.contains_key("org.new:util"),
+            resolver.packaging.get("org.old:lib:1.0").cloned(),
+        );
+
+        fs::remove_dir_all(&base).ok();
+
+        assert_eq!(result, (true, Some("1.0".to_string()), true, Some("pom".to_string())));
+    }
+
+    #[test]
+    fn reports_invalid_root_coordinates() {
+        let base = std::env::temp_dir().join(format!("spawn-test-coords-{}", std::process::id()));
+        fs::remove_dir_all(&base).ok();
+
+        let mut resolver = Resolver::new(Vec::new(), &base);
+        resolver.offline = true;
+        resolver.resolve(vec!["org.a:1.0".to_string(), "org.a:lib:".to_string(), "org.a:lib:1.0@".to_string()]);
+
+        fs::remove_dir_all(&base).ok();
+
+        let reasons: Vec<String> = resolver.unresolved.iter()
+            .map(|f| match &f.error {
+                ResolveError::InvalidCoordinates { reason, .. } if f.path.is_empty() => reason.clone(),
+                other => panic!("unexpected {}", other),
+            })
+            .collect();
+
+        assert_eq!(reasons, vec![
+            "expected group:name:version[:classifier][@ext]",
+            "no version",
+            "empty extension after @",
+        ]);
+
+        assert!(resolver.resolved_artifacts.is_empty());
+    }
+
+    #[test]
+    fn picks_latest_cached_snapshot() {
+        let base = std::env::temp_dir().join(format!("spawn-test-snapshot-{}", std::process::id()));
+        fs::remove_dir_all(&base).ok();
+
+        let resolver = Resolver::new(Vec::new(), &base);
+        let art = Artifact::new("org.a", "lib", "1.0-SNAPSHOT");
+
+        let dir = base.join("repository/org/a/lib/1.0-SNAPSHOT");
+        fs::create_dir_all(&dir).unwrap();
+
+        for name in [
+            "lib-1.0-SNAPSHOT.jar",
+            "lib-1.0-SNAPSHOT-sources.jar",
+            "lib-1.0-20250101.120000-9.jar",
+            "lib-1.0-20250101.120000-10.jar",
+            "lib-1.0-20241231.235959-11.jar",
+            "lib-1.0-20250101.120000-12-sources.jar",
+            "lib-1.0-20250101.120000-12.pom",
+        ] {
+            fs::write(dir.join(name), "").unwrap();
+        }
+
+        let jar = resolver.cached_snapshot(&art, "jar");
+        let pom = resolver.cached_snapshot(&art, "pom");
+
+        fs::remove_file(dir.join("lib-1.0-20250101.120000-12.pom")).unwrap();
+        let missing = resolver.cached_snapshot(&art, "pom").is_err();
+
+        fs::remove_dir_all(&base).ok();
+
+        assert_eq!(jar.unwrap(), "1.0-20250101.120000-10");
+        assert_eq!(pom.unwrap(), "1.0-20250101.120000-12");
+        assert!(missing);
+    }
+
+    #[test]
+    fn snapshot_builds() {
+        assert_eq!(snapshot_build("1.0-SNAPSHOT", "1.0-SNAPSHOT"), Some((String::new(), 0)));
+        assert_eq!(snapshot_build("1.0-SNAPSHOT", "1.0-20250101.120000-3"), Some(("20250101.120000".to_string(), 3)));
+
+        assert_eq!(snapshot_build("1.0-SNAPSHOT", "1.0-SNAPSHOT-sources"), None);
+        assert_eq!(snapshot_build("1.0-SNAPSHOT", "1.0-20250101.120000-3-sources"), None);
+        assert_eq!(snapshot_build("1.0-SNAPSHOT", "1.0.1-20250101.120000-3"), None);
+        assert_eq!(snapshot_build("1.0-SNAPSHOT", "1.0-2025.12-3"), None);
+    }
+}
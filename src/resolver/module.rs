@@ -0,0 +1,373 @@
+// Copyright (c) 2025 Spawn
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// https://www.eclipse.org/legal/epl-2.0/
+// SPDX-License-Identifier: EPL-2.0
+
+use std::collections::HashMap;
+
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::resolver::artifact::Artifact;
+use crate::resolver::graph::Exclusion;
+use crate::resolver::pom::DependencyEntry;
+
+/// Такой комментарий Gradle пишет в pom если рядом опубликован .module.
+/// Без него .module не запрашивается, чтобы не ловить 404 на каждом артефакте
+pub const GRADLE_METADATA_MARKER: &str = "published-with-gradle-metadata";
+
+/// Gradle Module Metadata (.module)
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Module {
+    pub format_version: String,
+
+    #[serde(default)]
+    pub variants: Vec<Variant>,
+}
+
+/// Один вариант компонента: api или runtime, для JVM, Android, JS и т.д.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Variant {
+    pub name: String,
+
+    #[serde(default)]
+    pub attributes: HashMap<String, Value>,
+
+    #[serde(default)]
+    pub dependencies: Vec<ModuleDependency>,
+
+    #[serde(default)]
+    pub dependency_constraints: Vec<ModuleDependency>,
+
+    /// Пусто если вариант предоставляет только group:name самого компонента
+    #[serde(default)]
+    pub capabilities: Vec<Capability>,
+
+    /// Вариант на самом деле опубликован другим компонентом (так
+    /// устроены Kotlin Multiplatform библиотеки)
+    #[serde(rename = "available-at")]
+    pub available_at: Option<AvailableAt>,
+
+    #[serde(default)]
+    pub files: Vec<ModuleFile>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ModuleDependency {
+    pub group: String,
+    pub module: String,
+
+    #[serde(default)]
+    pub version: VersionSpec,
+
+    #[serde(default)]
+    pub excludes: Vec<ModuleExclude>,
+
+    #[serde(default)]
+    pub attributes: HashMap<String, Value>,
+}
+
+/// strictly это жёсткое требование, requires и prefers мягкие
+#[derive(Debug, Default, Deserialize)]
+pub struct VersionSpec {
+    pub strictly: Option<String>,
+    pub requires: Option<String>,
+    pub prefers: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ModuleExclude {
+    pub group: String,
+    pub module: String,
+}
+
+/// Что компонент собой представляет. Два разных артефакта с одной
+/// capability в графе быть не могут
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Capability {
+    pub group: String,
+    pub name: String,
+
+    #[serde(default)]
+    pub version: String,
+}
+
+impl Capability {
+    pub fn id(&self) -> String {
+        format!("{}:{}", self.group, self.name)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ModuleFile {
+    pub name: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AvailableAt {
+    pub group: String,
+    pub module: String,
+    pub version: String,
+}
+
+/// Выбранный вариант в том виде в котором его понимает resolver
+#[derive(Debug)]
+pub struct SelectedVariant {
+    pub name: String,
+
+    /// Расширение файла варианта, pom если файлов у него нет
+    pub extension: String,
+
+    pub dependencies: Vec<DependencyEntry>,
+    pub constraints: Vec<DependencyEntry>,
+    pub capabilities: Vec<Capability>,
+
+    /// Зависимости на platform (BOM). Своих файлов у них нет, из их
+    /// dependencyManagement берутся версии зависимостей без версии
+    pub platforms: Vec<Artifact>,
+}
+
+pub fn parse(json: &str) -> Result<Module, String> {
+    let module: Module = serde_json::from_str(json).map_err(|e| e.to_string())?;
+
+    if !module.format_version.starts_with("1.") {
+        return Err(format!("unsupported format version {}", module.format_version));
+    }
+
+    Ok(module)
+}
+
+impl Module {
+    /// Вариант для APK с его зависимостями. None если подходящего нет
+    pub fn select(&self) -> Option<SelectedVariant> {
+        let variant = self.runtime_variant()?;
+
+        Some(SelectedVariant {
+            name: variant.name.clone(),
+            extension: variant.files.first()
+                .and_then(|f| f.name.rsplit_once('.'))
+                .map_or("pom", |(_, ext)| ext)
+                .to_string(),
+            dependencies: self.dependencies(variant),
+            constraints: variant.dependency_constraints.iter().map(|c| c.entry(None)).collect(),
+            capabilities: variant.capabilities.clone(),
+            platforms: variant.dependencies.iter()
+                .filter(|dep| variant.available_at.is_none() && dep.is_platform())
+                .map(|dep| dep.entry(None).artifact)
+                .collect(),
+        })
+    }
+
+    /// Вариант для APK: runtime, а если его нет то api. Android важнее
+    /// JVM, варианты для других платформ, документация и platform (BOM)
+    /// не подходят
+    fn runtime_variant(&self) -> Option<&Variant> {
+        self.best_variant(|usage| match usage {
+            "java-runtime" | "kotlin-runtime" => Some(2),
+            "java-api" | "kotlin-api" => Some(1),
+            _ => None,
+        })
+    }
+
+    /// Вариант для компиляции. Зависимости которых в нём нет нужны только
+    /// в runtime
+    fn api_variant(&self) -> Option<&Variant> {
+        self.best_variant(|usage| match usage {
+            "java-api" | "kotlin-api" => Some(1),
+            _ => None,
+        })
+    }
+
+    fn best_variant(&self, usage_rank: impl Fn(&str) -> Option<u8>) -> Option<&Variant> {
+        let mut best: Option<(&Variant, (u8, u8, u8))> = None;
+
+        for variant in &self.variants {
+            let Some(rank) = variant.rank(&usage_rank) else {
+                continue;
+            };
+
+            if best.is_none_or(|(_, best_rank)| rank > best_rank) {
+                best = Some((variant, rank));
+            }
+        }
+
+        best.map(|(variant, _)| variant)
+    }
+
+    /// Зависимости варианта в виде записей pom: то чего нет в api
+    /// варианте получает scope runtime
+    fn dependencies(&self, variant: &Variant) -> Vec<DependencyEntry> {
+        if let Some(target) = &variant.available_at {
+            return vec![DependencyEntry {
+                artifact: Artifact::new(&target.group, &target.module, &target.version),
+                scope: None,
+                entry_type: None,
+                optional: false,
+                exclusions: Vec::new(),
+            }];
+        }
+
+        let api = self.api_variant();
+
+        variant.dependencies.iter()
+            // Зависимость на platform даёт только версии, своих файлов у неё нет
+            .filter(|dep| !dep.is_platform())
+            .map(|dep| {
+                let compile = api.is_none_or(|api| {
+                    api.dependencies.iter().any(|d| d.group == dep.group && d.module == dep.module)
+                });
+
+                dep.entry(if compile { None } else { Some("runtime".to_string()) })
+            })
+            .collect()
+    }
+}
+
+impl Variant {
+    pub fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes.get(name).and_then(Value::as_str)
+    }
+
+    /// Насколько вариант подходит: usage, платформа, окружение. None если
+    /// не подходит вовсе
+    fn rank(&self, usage_rank: impl Fn(&str) -> Option<u8>) -> Option<(u8, u8, u8)> {
+        if self.attribute("org.gradle.category").is_some_and(|c| c != "library") {
+            return None;
+        }
+
+        if self.attributes.contains_key("org.gradle.docstype") {
+            return None;
+        }
+
+        let usage = usage_rank(self.attribute("org.gradle.usage")?)?;
+
+        let platform = match self.attribute("org.jetbrains.kotlin.platform.type") {
+            Some("androidJvm") => 2,
+            Some("jvm") => 1,
+            None => 1,
+            Some(_) => return None,
+        };
+
+        // Guava публикует отдельно android и standard-jvm варианты
+        let environment = match self.attribute("org.gradle.jvm.environment") {
+            Some("android") => 1,
+            _ => 0,
+        };
+
+        Some((usage, platform, environment))
+    }
+}
+
+impl ModuleDependency {
+    pub fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes.get(name).and_then(Value::as_str)
+    }
+
+    /// platform или enforced-platform
+    pub fn is_platform(&self) -> bool {
+        self.attribute("org.gradle.category").is_some_and(|c| c.ends_with("platform"))
+    }
+
+    /// Запись pom с версией в нотации Maven: strictly 1.0 становится
+    /// диапазоном [1.0], без версии остаётся пустой
+    pub fn entry(&self, scope: Option<String>) -> DependencyEntry {
+        let version = match &self.version.strictly {
+            Some(v) if !v.starts_with('[') && !v.starts_with('(') => format!("[{}]", v),
+            Some(v) => v.clone(),
+            None => self.version.requires.clone().or_else(|| self.version.prefers.clone()).unwrap_or_default(),
+        };
+
+        DependencyEntry {
+            artifact: Artifact::new(&self.group, &self.module, &version),
+            scope,
+            entry_type: None,
+            optional: false,
+            exclusions: self.excludes.iter()
+                .map(|e| Exclusion { group: e.group.clone(), name: e.module.clone() })
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn variant(name: &str, usage: &str, platform: Option<&str>, deps: &[&str]) -> String {
+        let platform = platform
+            .map(|p| format!(r#", "org.jetbrains.kotlin.platform.type": "{}""#, p))
+            .unwrap_or_default();
+
+        let deps: Vec<String> = deps.iter()
+            .map(|d| format!(r#"{{"group": "g", "module": "{}", "version": {{"requires": "1.0"}}}}"#, d))
+            .collect();
+
+        format!(
+            r#"{{"name": "{}", "attributes": {{"org.gradle.category": "library", "org.gradle.usage": "{}"{}}}, "dependencies": [{}], "files": [{{"name": "{}.aar"}}]}}"#,
+            name, usage, platform, deps.join(", "), name
+        )
+    }
+
+    fn module(variants: &[String]) -> Module {
+        parse(&format!(r#"{{"formatVersion": "1.1", "variants": [{}]}}"#, variants.join(", "))).unwrap()
+    }
+
+    #[test]
+    fn android_before_jvm() {
+        let module = module(&[
+            variant("jvmRuntime", "java-runtime", Some("jvm"), &[]),
+            variant("androidRuntime", "java-runtime", Some("androidJvm"), &[]),
+            variant("jsRuntime", "kotlin-runtime", Some("js"), &[]),
+        ]);
+
+        assert_eq!(module.select().unwrap().name, "androidRuntime");
+    }
+
+    #[test]
+    fn runtime_before_api() {
+        let module = module(&[
+            variant("androidApi", "java-api", Some("androidJvm"), &["a"]),
+            variant("jvmRuntime", "java-runtime", Some("jvm"), &["a", "b"]),
+        ]);
+
+        let selected = module.select().unwrap();
+        assert_eq!(selected.name, "jvmRuntime");
+        assert_eq!(selected.extension, "aar");
+
+        // b есть только в runtime варианте
+        let scopes: Vec<_> = selected.dependencies.iter()
+            .map(|d| (d.artifact.name.as_str(), d.scope.as_deref()))
+            .collect();
+
+        assert_eq!(scopes, vec![("a", None), ("b", Some("runtime"))]);
+    }
+
+    #[test]
+    fn no_suitable_variant() {
+        let module = module(&[variant("jsRuntime", "kotlin-runtime", Some("js"), &[])]);
+        assert!(module.select().is_none());
+    }
+
+    #[test]
+    fn platform_dependency_gives_versions_only() {
+        let module = parse(r#"{"formatVersion": "1.1", "variants": [{
+            "name": "runtime",
+            "attributes": {"org.gradle.usage": "java-runtime"},
+            "dependencies": [
+                {"group": "g", "module": "bom", "version": {"requires": "1.0"}, "attributes": {"org.gradle.category": "platform"}},
+                {"group": "g", "module": "a"},
+                {"group": "g", "module": "b", "version": {"strictly": "2.0"}}
+            ]
+        }]}"#).unwrap();
+
+        let selected = module.select().unwrap();
+        let deps: Vec<String> = selected.dependencies.iter().map(|d| d.artifact.to_string()).collect();
+
+        assert_eq!(deps, vec!["g:a:", "g:b:[2.0]"]);
+        assert_eq!(selected.platforms.iter().map(|p| p.to_string()).collect::<Vec<_>>(), vec!["g:bom:1.0"]);
+        assert_eq!(selected.extension, "pom");
+    }
+}
@@ -5,19 +5,73 @@
 // SPDX-License-Identifier: EPL-2.0
 
 use quick_xml::{events::Event, Reader};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fs;
 
 use crate::resolver::artifact::Artifact;
+use crate::resolver::error::ResolveError;
+use crate::resolver::graph::Exclusion;
+use crate::resolver::version::VersionRange;
 use crate::resolver::Resolver;
 
+/// Версия JDK для <activation> профилей: сборка идёт с -target 1.8
+const JDK_VERSION: &str = "1.8";
+
+const PROFILE: &str = "project/profiles/profile";
+
 #[derive(Debug, Default)]
 pub struct Pom {
+    /// Координаты самого pom. groupId и version могут быть не указаны,
+    /// тогда они берутся из parent
+    pub group_id: Option<String>,
+    pub artifact_id: Option<String>,
+    pub version: Option<String>,
+
     pub properties: HashMap<String, String>,
     pub dependencies: Vec<DependencyEntry>,
     pub dep_management: Vec<DependencyEntry>,
     pub repositories: Vec<String>,
     pub parent: Option<Artifact>,
+
+    /// <packaging>, None если не указан (тогда это jar)
+    pub packaging: Option<String>,
+
+    /// <profiles>, после активации остаются только неактивные
+    pub profiles: Vec<Profile>,
+
+    /// Артефакт переехал на другие координаты
+    pub relocation: Option<Relocation>,
+}
+
+#[derive(Debug, Default)]
+pub struct Profile {
+    pub id: String,
+    pub activation: Activation,
+
+    /// Что профиль добавляет в pom: свойства, зависимости, dependencyManagement
+    /// и репозитории
+    pub pom: Pom,
+}
+
+#[derive(Debug, Default)]
+pub struct Activation {
+    pub active_by_default: bool,
+    pub jdk: Option<String>,
+    pub property_name: Option<String>,
+    pub property_value: Option<String>,
+
+    /// os, file и другие условия которые здесь не проверяются. Такой
+    /// профиль не активируется
+    pub unsupported: bool,
+}
+
+/// <distributionManagement><relocation>. Что не указано, то не меняется
+#[derive(Debug, Default)]
+pub struct Relocation {
+    pub group_id: Option<String>,
+    pub artifact_id: Option<String>,
+    pub version: Option<String>,
+    pub message: Option<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -25,9 +79,17 @@ pub struct DependencyEntry {
     pub artifact: Artifact,
     pub scope: Option<String>,
     pub entry_type: Option<String>,
+
+    /// <optional>true</optional>: нужна только самому артефакту, дальше по
+    /// графу не идёт
+    pub optional: bool,
+
+    pub exclusions: Vec<Exclusion>,
 }
 
-pub fn parse(xml: &str, _current_art: &Artifact) -> Pom {
+/// Разбирает pom. Ошибка если это не XML или корень не <project> (например
+/// репозиторий отдал HTML страницу вместо файла)
+pub fn parse(xml: &str) -> Result<Pom, String> {
     let mut reader = Reader::from_str(xml);
     reader.trim_text(true);
 
@@ -40,6 +102,12 @@ pub fn parse(xml: &str, _current_art: &Artifact) -> Pom {
     let mut cur_v = String::new();
     let mut cur_s = String::new();
     let mut cur_t = String::new();
+    let mut cur_c = String::new();
+    let mut cur_optional = false;
+    let mut cur_exclusions = Vec::new();
+
+    let mut cur_ex_g = String::new();
+    let mut cur_ex_a = String::new();
 
     let mut cur_p_g = String::new();
     let mut cur_p_a = String::new();
@@ -47,14 +115,32 @@ pub fn parse(xml: &str, _current_art: &Artifact) -> Pom {
 
     let mut cur_repo_url = String::new();
     let mut section = String::new();
+    let mut root = None;
+    let mut profile: Option<Profile> = None;
 
     loop {
         match reader.read_event_into(&mut buf) {
+            Ok(Event::Empty(e)) if root.is_none() => {
+                root = Some(String::from_utf8_lossy(e.local_name().as_ref()).into_owned());
+            }
+
             Ok(Event::Start(e)) => {
                 let name = String::from_utf8_lossy(e.local_name().as_ref()).into_owned();
+                root.get_or_insert_with(|| name.clone());
                 path.push(name.clone());
 
-                let full = path.join("/");
+                let raw = path.join("/");
+
+                if raw == PROFILE {
+                    profile = Some(Profile::default());
+                } else if let Some(profile) = &mut profile
+                    && raw.strip_prefix(PROFILE).and_then(|p| p.strip_prefix("/activation/")) == Some(name.as_str())
+                    && !matches!(name.as_str(), "activeByDefault" | "jdk" | "property")
+                {
+                    profile.activation.unsupported = true;
+                }
+
+                let (full, _) = scoped(&raw, &mut pom, &mut profile);
                 if full == "project/dependencyManagement/dependencies" {
                     section = "dep_management".to_string();
                 } else if full == "project/dependencies" {
@@ -64,73 +150,119 @@ pub fn parse(xml: &str, _current_art: &Artifact) -> Pom {
 
             Ok(Event::Text(e)) => {
                 let val = e.unescape().unwrap_or_default().into_owned();
-                let full_path = path.join("/");
+                let raw_path = path.join("/");
+
+                if let Some(profile) = &mut profile {
+                    match raw_path.strip_prefix(PROFILE) {
+                        Some("/id") => profile.id = val.clone(),
+                        Some("/activation/activeByDefault") => profile.activation.active_by_default = val == "true",
+                        Some("/activation/jdk") => profile.activation.jdk = Some(val.clone()),
+                        Some("/activation/property/name") => profile.activation.property_name = Some(val.clone()),
+                        Some("/activation/property/value") => profile.activation.property_value = Some(val.clone()),
+                        _ => {}
+                    }
+                }
+
+                let (full_path, target) = scoped(&raw_path, &mut pom, &mut profile);
 
                 if full_path.starts_with("project/properties/") {
                     let key = full_path
                         .strip_prefix("project/properties/")
                         .unwrap()
                         .to_string();
-                    pom.properties.insert(key, val.clone());
+                    target.properties.insert(key, val.clone());
                 }
 
                 match full_path.as_str() {
+                    "project/groupId" => target.group_id = Some(val.clone()),
+                    "project/artifactId" => target.artifact_id = Some(val.clone()),
+                    "project/version" => target.version = Some(val.clone()),
                     "project/parent/groupId" => cur_p_g = val.clone(),
                     "project/parent/artifactId" => cur_p_a = val.clone(),
                     "project/parent/version" => cur_p_v = val.clone(),
                     "project/repositories/repository/url" => cur_repo_url = val.clone(),
+                    "project/packaging" => target.packaging = Some(val.clone()),
                     _ => {}
                 }
 
-                if full_path.starts_with("project/dependencies/dependency/")
-                    || full_path.starts_with("project/dependencyManagement/dependencies/dependency/")
-                {
-                    match full_path.rsplit('/').next().unwrap() {
-                        "groupId" => cur_g = val.clone(),
-                        "artifactId" => cur_a = val.clone(),
-                        "version" => cur_v = val.clone(),
-                        "scope" => cur_s = val.clone(),
-                        "type" => cur_t = val.clone(),
+                if let Some(field) = full_path.strip_prefix("project/distributionManagement/relocation/") {
+                    let relocation = target.relocation.get_or_insert_default();
+
+                    match field {
+                        "groupId" => relocation.group_id = Some(val.clone()),
+                        "artifactId" => relocation.artifact_id = Some(val.clone()),
+                        "version" => relocation.version = Some(val.clone()),
+                        "message" => relocation.message = Some(val.clone()),
                         _ => {}
                     }
                 }
+
+                let dep_field = full_path.strip_prefix("project/dependencies/dependency/")
+                    .or_else(|| full_path.strip_prefix("project/dependencyManagement/dependencies/dependency/"));
+
+                match dep_field {
+                    Some("groupId") => cur_g = val.clone(),
+                    Some("artifactId") => cur_a = val.clone(),
+                    Some("version") => cur_v = val.clone(),
+                    Some("scope") => cur_s = val.clone(),
+                    Some("type") => cur_t = val.clone(),
+                    Some("classifier") => cur_c = val.clone(),
+                    Some("optional") => cur_optional = val == "true",
+                    Some("exclusions/exclusion/groupId") => cur_ex_g = val.clone(),
+                    Some("exclusions/exclusion/artifactId") => cur_ex_a = val.clone(),
+                    _ => {}
+                }
             }
 
             Ok(Event::End(_)) => {
-                let full_path = path.join("/");
+                let raw_path = path.join("/");
+
+                if raw_path == PROFILE && let Some(profile) = profile.take() {
+                    pom.profiles.push(profile);
+                }
+
+                let (full_path, target) = scoped(&raw_path, &mut pom, &mut profile);
 
                 if full_path == "project/parent" && !cur_p_g.is_empty() && !cur_p_a.is_empty() && !cur_p_v.is_empty() {
-                    pom.parent = Some(Artifact::new(&cur_p_g, &cur_p_a, &cur_p_v));
+                    target.parent = Some(Artifact::new(&cur_p_g, &cur_p_a, &cur_p_v));
                     cur_p_g.clear();
                     cur_p_a.clear();
                     cur_p_v.clear();
                 } else if full_path == "project/repositories/repository" && !cur_repo_url.is_empty() {
-                    pom.repositories.push(cur_repo_url.clone());
+                    target.repositories.push(cur_repo_url.clone());
                     cur_repo_url.clear();
+                } else if full_path.ends_with("/dependency/exclusions/exclusion") {
+                    // Без artifactId исключается вся группа
+                    if !cur_ex_g.is_empty() {
+                        cur_exclusions.push(Exclusion {
+                            group: cur_ex_g.clone(),
+                            name: if cur_ex_a.is_empty() { "*".to_string() } else { cur_ex_a.clone() },
+                        });
+                    }
+
+                    cur_ex_g.clear();
+                    cur_ex_a.clear();
                 } else if full_path == "project/dependencies/dependency"
                     || full_path == "project/dependencyManagement/dependencies/dependency"
                 {
-                    if cur_g.is_empty() || cur_a.is_empty() || cur_a == "*" {
-                        // Пропускаем invalid или wildcard зависимости из BOM
-                        cur_g.clear();
-                        cur_a.clear();
-                        cur_v.clear();
-                        cur_s.clear();
-                        cur_t.clear();
-                        continue;
-                    }
+                    // Пропускаем invalid или wildcard зависимости из BOM
+                    if !cur_g.is_empty() && !cur_a.is_empty() && cur_a != "*" {
+                        let mut artifact = Artifact::new(&cur_g, &cur_a, &cur_v);
+                        artifact.classifier = if cur_c.is_empty() { None } else { Some(cur_c.clone()) };
+
+                        let entry = DependencyEntry {
+                            artifact,
+                            scope: if cur_s.is_empty() { None } else { Some(cur_s.clone()) },
+                            entry_type: if cur_t.is_empty() { None } else { Some(cur_t.clone()) },
+                            optional: cur_optional,
+                            exclusions: std::mem::take(&mut cur_exclusions),
+                        };
 
-                    let artifact = Artifact::new(&cur_g, &cur_a, &cur_v);
-                    let entry = DependencyEntry {
-                        artifact,
-                        scope: if cur_s.is_empty() { None } else { Some(cur_s.clone()) },
-                        entry_type: if cur_t.is_empty() { None } else { Some(cur_t.clone()) },
-                    };
-
-                    if section == "dependencies" {
-                        pom.dependencies.push(entry);
-                    } else if section == "dep_management" {
-                        pom.dep_management.push(entry);
+                        if section == "dependencies" {
+                            target.dependencies.push(entry);
+                        } else if section == "dep_management" {
+                            target.dep_management.push(entry);
+                        }
                     }
 
                     cur_g.clear();
@@ -138,6 +270,9 @@ pub fn parse(xml: &str, _current_art: &Artifact) -> Pom {
                     cur_v.clear();
                     cur_s.clear();
                     cur_t.clear();
+                    cur_c.clear();
+                    cur_optional = false;
+                    cur_exclusions.clear();
                 } else if full_path == "project/dependencies"
                     || full_path == "project/dependencyManagement/dependencies"
                 {
@@ -148,45 +283,436 @@ pub fn parse(xml: &str, _current_art: &Artifact) -> Pom {
             }
 
             Ok(Event::Eof) => break,
+
+            Err(e) => return Err(format!("position {}: {}", reader.buffer_position(), e)),
+
             _ => (),
         }
 
         buf.clear();
     }
 
-    pom
+    match root.as_deref() {
+        Some("project") => Ok(pom),
+        Some(other) => Err(format!("root element is <{}>, not <project>", other)),
+        None => Err("empty file".to_string()),
+    }
+}
+
+/// Путь элемента и pom в который он попадает: содержимое профиля
+/// разбирается как отдельный pom, project/profiles/profile/dependencies
+/// становится project/dependencies
+fn scoped<'a>(path: &str, pom: &'a mut Pom, profile: &'a mut Option<Profile>) -> (String, &'a mut Pom) {
+    match (path.strip_prefix(PROFILE).and_then(|p| p.strip_prefix('/')), profile) {
+        (Some(rest), Some(profile)) => (format!("project/{}", rest), &mut profile.pom),
+        _ => (path.to_string(), pom),
+    }
+}
+
+/// Добавляет в pom содержимое активных профилей и возвращает их id.
+/// activeByDefault профили активны только если не сработал ни один другой
+pub fn activate_profiles(pom: &mut Pom) -> Vec<String> {
+    let profiles = std::mem::take(&mut pom.profiles);
+    let triggered: Vec<Option<bool>> = profiles.iter().map(|p| p.activation.triggered()).collect();
+    let any_triggered = triggered.contains(&Some(true));
+
+    let mut activated = Vec::new();
+
+    for (profile, triggered) in profiles.into_iter().zip(triggered) {
+        let active = match triggered {
+            Some(active) => active,
+            None => profile.activation.active_by_default && !any_triggered,
+        };
+
+        if !active {
+            pom.profiles.push(profile);
+            continue;
+        }
+
+        pom.properties.extend(profile.pom.properties);
+        pom.dependencies.extend(profile.pom.dependencies);
+        pom.dep_management.extend(profile.pom.dep_management);
+        pom.repositories.extend(profile.pom.repositories);
+
+        activated.push(profile.id);
+    }
+
+    activated
+}
+
+impl Activation {
+    /// Выполнены ли условия профиля (все сразу, как в Maven). None если
+    /// условий нет, тогда решает activeByDefault
+    fn triggered(&self) -> Option<bool> {
+        if self.unsupported {
+            return Some(false);
+        }
+
+        let mut conditions = Vec::new();
+
+        if let Some(jdk) = &self.jdk {
+            conditions.push(jdk_matches(jdk));
+        }
+
+        if let Some(name) = &self.property_name {
+            conditions.push(property_matches(name, self.property_value.as_deref()));
+        }
+
+        if conditions.is_empty() {
+            None
+        } else {
+            Some(conditions.iter().all(|c| *c))
+        }
+    }
+}
+
+/// <jdk>: префикс версии (1.8), диапазон ([1.8,11)) или отрицание (!1.8)
+fn jdk_matches(spec: &str) -> bool {
+    let (negate, spec) = spec.strip_prefix('!').map_or((false, spec), |s| (true, s));
+
+    let matches = if VersionRange::is_range(spec) {
+        VersionRange::parse(spec).is_ok_and(|r| r.contains(JDK_VERSION))
+    } else {
+        JDK_VERSION.starts_with(spec.trim())
+    };
+
+    negate != matches
+}
+
+/// <property> проверяет системные свойства. У нас из них есть только
+/// java.version, остальные считаются не заданными
+fn property_matches(name: &str, value: Option<&str>) -> bool {
+    let (negate_name, name) = name.strip_prefix('!').map_or((false, name), |n| (true, n));
+    let actual = (name == "java.version").then_some(JDK_VERSION);
+
+    match value.filter(|v| !v.is_empty()) {
+        Some(value) => {
+            let (negate_value, value) = value.strip_prefix('!').map_or((false, value), |v| (true, v));
+            negate_value != (actual == Some(value))
+        }
+
+        None => negate_name != actual.is_some(),
+    }
+}
+
+impl DependencyEntry {
+    /// Совпадает ли запись dependencyManagement с зависимостью. Ключ как в
+    /// Maven: group, name, type и classifier
+    pub fn manages(&self, dep: &DependencyEntry) -> bool {
+        self.artifact.group == dep.artifact.group
+            && self.artifact.name == dep.artifact.name
+            && self.artifact.classifier == dep.artifact.classifier
+            && self.entry_type.as_deref().unwrap_or("jar") == dep.entry_type.as_deref().unwrap_or("jar")
+    }
 }
 
-pub fn process_imports(dep_man: &mut Vec<DependencyEntry>, resolver: &Resolver) {
+/// Заменяет BOM с scope import на их dependencyManagement. BOM который уже
+/// импортирован пропускается, как и BOM из chain (тех что импортируют этот),
+/// поэтому взаимные импорты не зацикливаются
+pub fn process_imports(
+    dep_man: &mut Vec<DependencyEntry>,
+    resolver: &Resolver,
+    chain: &[Artifact],
+) -> Result<(), ResolveError> {
+    let mut imported = HashSet::new();
+
     let mut i = 0;
     while i < dep_man.len() {
         let entry = dep_man[i].clone();
         if entry.entry_type.as_deref() == Some("pom") && entry.scope.as_deref() == Some("import") {
-            if let Ok(pom_path) = resolver.fetch_artifact(&entry.artifact, "pom") {
-                if let Ok(xml) = fs::read_to_string(pom_path) {
-                    let imported = parse(&xml, &entry.artifact);
-                    dep_man.extend(imported.dep_management);
-                }
+            if !chain.contains(&entry.artifact) && imported.insert(entry.artifact.clone()) {
+                let pom_path = resolver.fetch_artifact(&entry.artifact, "pom")?;
+                let xml = fs::read_to_string(pom_path).map_err(|e| ResolveError::Io(e.to_string()))?;
+
+                let bom = resolver.get_effective_pom(&entry.artifact, &xml, chain)?;
+                dep_man.extend(bom.dep_management);
             }
+
             dep_man.remove(i);
         } else {
             i += 1;
         }
     }
+
+    Ok(())
+}
+
+/// Подставляет свойства во все значения pom после наследования от parent.
+/// Кроме <properties> доступны project.groupId, project.artifactId,
+/// project.version, project.parent.* и устаревшие pom.*. Формы без
+/// префикса Maven не поддерживает, это обычные свойства
+pub fn interpolate_pom(pom: &mut Pom, art: &Artifact) -> Result<(), ResolveError> {
+    let parent = pom.parent.clone();
+
+    let group = pom.group_id.clone()
+        .or_else(|| parent.as_ref().map(|p| p.group.clone()))
+        .unwrap_or_else(|| art.group.clone());
+
+    let name = pom.artifact_id.clone().unwrap_or_else(|| art.name.clone());
+
+    let version = pom.version.clone()
+        .or_else(|| parent.as_ref().map(|p| p.version.clone()))
+        .unwrap_or_else(|| art.version.clone());
+
+    let mut props = pom.properties.clone();
+
+    for prefix in ["project.", "pom."] {
+        props.insert(format!("{}groupId", prefix), group.clone());
+        props.insert(format!("{}artifactId", prefix), name.clone());
+        props.insert(format!("{}version", prefix), version.clone());
+    }
+
+    if let Some(parent) = &parent {
+        props.insert("project.parent.groupId".to_string(), parent.group.clone());
+        props.insert("project.parent.artifactId".to_string(), parent.name.clone());
+        props.insert("project.parent.version".to_string(), parent.version.clone());
+    }
+
+    let expand = |value: &mut String| -> Result<(), ResolveError> {
+        *value = interpolate(value, &props)?;
+        Ok(())
+    };
+
+    for entry in pom.dependencies.iter_mut().chain(pom.dep_management.iter_mut()) {
+        expand(&mut entry.artifact.group)?;
+        expand(&mut entry.artifact.name)?;
+        expand(&mut entry.artifact.version)?;
+
+        for value in entry.artifact.classifier.iter_mut().chain(&mut entry.entry_type).chain(&mut entry.scope) {
+            expand(value)?;
+        }
+
+        for exclusion in &mut entry.exclusions {
+            expand(&mut exclusion.group)?;
+            expand(&mut exclusion.name)?;
+        }
+    }
+
+    for repo in &mut pom.repositories {
+        expand(repo)?;
+    }
+
+    if let Some(relocation) = &mut pom.relocation {
+        let fields = [&mut relocation.group_id, &mut relocation.artifact_id, &mut relocation.version];
+
+        for value in fields.into_iter().flatten() {
+            expand(value)?;
+        }
+    }
+
+    pom.group_id = Some(interpolate(&group, &props)?);
+    pom.artifact_id = Some(interpolate(&name, &props)?);
+    pom.version = Some(interpolate(&version, &props)?);
+    pom.properties = props;
+
+    Ok(())
+}
+
+/// Подставляет ${...} в value. Значения свойств тоже могут ссылаться на
+/// свойства. Неизвестные свойства остаются как есть, цикл это ошибка
+pub fn interpolate(value: &str, props: &HashMap<String, String>) -> Result<String, ResolveError> {
+    interpolate_with(value, props, &mut Vec::new())
+}
+
+/// stack это свойства которые сейчас раскрываются, по нему ловится цикл
+fn interpolate_with(value: &str, props: &HashMap<String, String>, stack: &mut Vec<String>) -> Result<String, ResolveError> {
+    let mut result = String::new();
+    let mut rest = value;
+
+    while let Some(start) = rest.find("${") {
+        let Some(len) = rest[start + 2..].find('}') else {
+            break;
+        };
+
+        let key = &rest[start + 2..start + 2 + len];
+        result.push_str(&rest[..start]);
+
+        if let Some(pos) = stack.iter().position(|k| k == key) {
+            let mut cycle = stack[pos..].to_vec();
+            cycle.push(key.to_string());
+            return Err(ResolveError::PropertyCycle(cycle));
+        }
+
+        match props.get(key) {
+            Some(v) => {
+                stack.push(key.to_string());
+                result.push_str(&interpolate_with(v, props, stack)?);
+                stack.pop();
+            }
+
+            None => result.push_str(&rest[start..start + 3 + len]),
+        }
+
+        rest = &rest[start + 3 + len..];
+    }
+
+    result.push_str(rest);
+    Ok(result)
 }
 
-pub fn resolve_val(val: &str, props: &HashMap<String, String>, art: &Artifact) -> String {
-    if val == "${project.groupId}" || val == "${groupId}" {
-        return art.group.clone();
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn props(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    fn project(body: &str) -> Pom {
+        parse(&format!("<project><modelVersion>4.0.0</modelVersion>{}</project>", body)).unwrap()
+    }
+
+    fn dependency(group: &str, name: &str, extra: &str) -> DependencyEntry {
+        let pom = project(&format!(
+            "<dependencies><dependency><groupId>{}</groupId><artifactId>{}</artifactId>{}</dependency></dependencies>",
+            group, name, extra
+        ));
+
+        pom.dependencies.into_iter().next().unwrap()
+    }
+
+    #[test]
+    fn interpolate_recursive() {
+        let props = props(&[("a", "${b}-x"), ("b", "${c}.${c}"), ("c", "1")]);
+
+        assert_eq!(interpolate("${a}", &props).unwrap(), "1.1-x");
+        assert_eq!(interpolate("v${c}/${unknown}", &props).unwrap(), "v1/${unknown}");
+    }
+
+    #[test]
+    fn interpolate_cycle() {
+        let props = props(&[("a", "${b}"), ("b", "x${a}"), ("self", "${self}")]);
+
+        match interpolate("${a}", &props) {
+            Err(ResolveError::PropertyCycle(chain)) => assert_eq!(chain, vec!["a", "b", "a"]),
+            other => panic!("expected cycle, got {:?}", other),
+        }
+
+        assert!(matches!(interpolate("${self}", &props), Err(ResolveError::PropertyCycle(_))));
+    }
+
+    #[test]
+    fn interpolate_project_properties() {
+        let mut pom = project(
+            "<parent><groupId>org.p</groupId><artifactId>parent</artifactId><version>2.0</version></parent>\
+             <artifactId>lib</artifactId>\
+             <properties><version>9.9</version><dep.version>${project.version}</dep.version></properties>\
+             <dependencies>\
+               <dependency><groupId>${project.groupId}</groupId><artifactId>a</artifactId><version>${dep.version}</version></dependency>\
+               <dependency><groupId>org.x</groupId><artifactId>b</artifactId><version>${version}</version></dependency>\
+               <dependency><groupId>org.x</groupId><artifactId>c</artifactId><version>${project.parent.version}</version></dependency>\
+             </dependencies>",
+        );
+
+        interpolate_pom(&mut pom, &Artifact::new("org.p", "lib", "2.0")).unwrap();
+
+        let deps: Vec<String> = pom.dependencies.iter().map(|d| d.artifact.to_string()).collect();
+
+        // version это обычное свойство, а не project.version
+        assert_eq!(deps, vec!["org.p:a:2.0", "org.x:b:9.9", "org.x:c:2.0"]);
+        assert_eq!(pom.group_id.as_deref(), Some("org.p"));
+    }
+
+    #[test]
+    fn manages_by_classifier_and_type() {
+        let managed = dependency("g", "a", "<version>1.0</version><classifier>natives</classifier>");
+        let managed_jar = dependency("g", "a", "<version>2.0</version><type>jar</type>");
+
+        let plain = dependency("g", "a", "");
+        let natives = dependency("g", "a", "<classifier>natives</classifier>");
+        let aar = dependency("g", "a", "<type>aar</type>");
+
+        assert!(managed.manages(&natives));
+        assert!(!managed.manages(&plain));
+
+        // Без type это jar
+        assert!(managed_jar.manages(&plain));
+        assert!(!managed_jar.manages(&aar));
+        assert!(!managed_jar.manages(&natives));
     }
-    if val == "${project.version}" || val == "${version}" {
-        return art.version.clone();
+
+    fn profile(id: &str, activation: &str) -> String {
+        format!(
+            "<profile><id>{0}</id><activation>{1}</activation><properties><{0}>on</{0}></properties></profile>",
+            id, activation
+        )
+    }
+
+    fn active(profiles: &[String]) -> Vec<String> {
+        let mut pom = project(&format!("<profiles>{}</profiles>", profiles.concat()));
+        let activated = activate_profiles(&mut pom);
+
+        for id in &activated {
+            assert_eq!(pom.properties.get(id).map(String::as_str), Some("on"));
+        }
+
+        activated
+    }
+
+    #[test]
+    fn active_by_default_yields_to_triggered() {
+        let by_default = profile("dflt", "<activeByDefault>true</activeByDefault>");
+
+        assert_eq!(active(std::slice::from_ref(&by_default)), vec!["dflt"]);
+        assert_eq!(active(&[by_default.clone(), profile("jdk", "<jdk>1.8</jdk>")]), vec!["jdk"]);
+
+        // Не сработавший профиль activeByDefault не отключает
+        assert_eq!(active(&[by_default, profile("jdk11", "<jdk>11</jdk>")]), vec!["dflt"]);
+    }
+
+    #[test]
+    fn jdk_activation() {
+        let profiles = [
+            profile("prefix", "<jdk>1.8</jdk>"),
+            profile("other", "<jdk>11</jdk>"),
+            profile("range", "<jdk>[1.7,11)</jdk>"),
+            profile("not8", "<jdk>!1.8</jdk>"),
+            profile("not11", "<jdk>!11</jdk>"),
+        ];
+
+        assert_eq!(active(&profiles), vec!["prefix", "range", "not11"]);
+    }
+
+    #[test]
+    fn property_activation() {
+        let property = |id: &str, name: &str, value: Option<&str>| {
+            let value = value.map(|v| format!("<value>{}</value>", v)).unwrap_or_default();
+            profile(id, &format!("<property><name>{}</name>{}</property>", name, value))
+        };
+
+        let profiles = [
+            property("defined", "java.version", None),
+            property("undefined", "release", None),
+            property("not_defined", "!release", None),
+            property("not_java", "!java.version", None),
+            property("value", "java.version", Some("1.8")),
+            property("not_value", "java.version", Some("!1.8")),
+            property("other_value", "java.version", Some("!11")),
+        ];
+
+        assert_eq!(active(&profiles), vec!["defined", "not_defined", "value", "other_value"]);
     }
 
-    let mut result = val.to_string();
-    for (k, v) in props {
-        let pattern = format!("${{{}}}", k);
-        result = result.replace(&pattern, v);
+    #[test]
+    fn unsupported_activation() {
+        let os = profile("os", "<os><family>unix</family></os>");
+        assert_eq!(active(&[os, profile("dflt", "<activeByDefault>true</activeByDefault>")]), vec!["dflt"]);
     }
-    result
-}
\ No newline at end of file
+
+    #[test]
+    fn relocation() {
+        let mut pom = project(
+            "<artifactId>old</artifactId><properties><new.group>org.new</new.group></properties>\
+             <distributionManagement><relocation>\
+               <groupId>${new.group}</groupId><message>moved</message>\
+             </relocation></distributionManagement>",
+        );
+
+        interpolate_pom(&mut pom, &Artifact::new("org.old", "old", "1.0")).unwrap();
+
+        let relocation = pom.relocation.unwrap();
+        assert_eq!(relocation.group_id.as_deref(), Some("org.new"));
+        assert_eq!(relocation.artifact_id, None);
+        assert_eq!(relocation.message.as_deref(), Some("moved"));
+    }
+}
@@ -0,0 +1,273 @@
+// Copyright (c) 2025 Spawn
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// https://www.eclipse.org/legal/epl-2.0/
+// SPDX-License-Identifier: EPL-2.0
+
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use dirs::home_dir;
+use reqwest::blocking::RequestBuilder;
+use serde::Deserialize;
+
+use crate::logger;
+use crate::resolver::artifact::group_matches;
+
+/// Файл с учётными данными пользователя, в проект он никогда не попадает
+pub const CREDENTIALS_FILE: &str = "credentials.toml";
+
+#[derive(Debug, Clone)]
+pub enum Auth {
+    Basic { username: String, password: Option<String> },
+    Bearer(String),
+}
+
+/// Встроенные репозитории по умолчанию, в порядке опроса
+pub const DEFAULT_REPOSITORIES: &[(&str, &str)] = &[
+    ("google", "https://dl.google.com/dl/android/maven2/"),
+    ("central", "https://repo1.maven.org/maven2/"),
+
+    // Зеркало для РФ и Китая, обходит блокировки от гугла
+    ("huawei", "https://repo.huaweicloud.com/repository/maven/"),
+];
+
+/// Maven репозиторий. Имя есть у встроенных репозиториев и у записей
+/// [[repositories]], по нему ищутся учётные данные
+#[derive(Debug, Clone)]
+pub struct Repository {
+    pub name: Option<String>,
+    pub url: String,
+    pub auth: Option<Auth>,
+
+    /// Группы которые искать только здесь (androidx, com.google.*). Если
+    /// список пустой, то репозиторий обслуживает все группы кроме тех,
+    /// которые другие репозитории объявили своими через include
+    pub include: Vec<String>,
+
+    /// Группы которые никогда не запрашивать из этого репозитория
+    pub exclude: Vec<String>,
+}
+
+impl Repository {
+    pub fn new(url: &str) -> Self {
+        let mut url = url.trim().to_string();
+
+        if !url.ends_with('/') {
+            url.push('/');
+        }
+
+        Self {
+            name: None,
+            url,
+            auth: None,
+            include: Vec::new(),
+            exclude: Vec::new(),
+        }
+    }
+
+    /// Репозиторий в папке на диске (file://), например ~/.m2/repository
+    pub fn local(name: &str, path: &Path) -> Self {
+        Self {
+            name: Some(name.to_string()),
+            ..Self::new(&format!("file://{}", path.display()))
+        }
+    }
+
+    /// Папка на диске если это file:// репозиторий
+    pub fn local_path(&self) -> Option<PathBuf> {
+        file_url_path(&self.url)
+    }
+
+    /// Встроенный репозиторий по имени (google, central, huawei)
+    pub fn builtin(name: &str) -> Option<Self> {
+        DEFAULT_REPOSITORIES.iter()
+            .find(|(n, _)| *n == name)
+            .map(|(n, url)| Self {
+                name: Some(n.to_string()),
+                ..Self::new(url)
+            })
+    }
+
+    /// Явно ли репозиторий объявил группу своей через include
+    pub fn includes(&self, group: &str) -> bool {
+        self.include.iter().any(|p| group_matches(p, group))
+    }
+
+    pub fn excludes(&self, group: &str) -> bool {
+        self.exclude.iter().any(|p| group_matches(p, group))
+    }
+
+    /// Репозиторий с именем и учётными данными из окружения или
+    /// ~/.spawn/credentials.toml если они там есть
+    pub fn named(name: &str, url: &str) -> Result<Self, String> {
+        let mut repo = Self::new(url);
+
+        repo.name = Some(name.to_string());
+        repo.auth = credentials(name)?;
+
+        Ok(repo)
+    }
+
+    /// Добавляет к запросу авторизацию репозитория
+    pub fn authorize(&self, request: RequestBuilder) -> RequestBuilder {
+        match &self.auth {
+            Some(Auth::Basic { username, password }) => request.basic_auth(username, password.as_ref()),
+            Some(Auth::Bearer(token)) => request.bearer_auth(token),
+            None => request,
+        }
+    }
+}
+
+/// Зеркало из ~/.spawn/config.toml, подменяет подходящие под mirror_of
+/// репозитории (в том числе объявленные в pom)
+#[derive(Debug, Clone)]
+pub struct Mirror {
+    pub repository: Repository,
+    pub mirror_of: String,
+}
+
+impl Mirror {
+    /// Правила как у <mirrorOf> в Maven: "*" все, "external:*" все кроме
+    /// localhost и file://, "a,b" перечисленные, "!a" исключение.
+    /// Репозиторий сравнивается по имени или по URL. Папки на диске (file://)
+    /// никогда не подменяются, иначе "*" отрезал бы локально собранные библиотеки
+    pub fn matches(&self, repo: &Repository) -> bool {
+        if repo.url == self.repository.url || repo.local_path().is_some() {
+            return false;
+        }
+
+        let mut matched = false;
+
+        for pattern in self.mirror_of.split(',').map(str::trim) {
+            if let Some(excluded) = pattern.strip_prefix('!') {
+                if is_repository(repo, excluded) {
+                    return false;
+                }
+            } else if pattern == "*"
+                || (pattern == "external:*" && !is_local(repo))
+                || is_repository(repo, pattern)
+            {
+                matched = true;
+            }
+        }
+
+        matched
+    }
+}
+
+/// Подменяет репозиторий первым подходящим зеркалом. Фильтры групп
+/// остаются от исходного репозитория
+pub fn apply_mirrors(mirrors: &[Mirror], repo: Repository) -> Repository {
+    let Some(mirror) = mirrors.iter().find(|m| m.matches(&repo)) else {
+        return repo;
+    };
+
+    debug!("Using mirror {} for {}", mirror.repository.url, repo.url);
+
+    Repository {
+        include: repo.include,
+        exclude: repo.exclude,
+        ..mirror.repository.clone()
+    }
+}
+
+fn is_repository(repo: &Repository, pattern: &str) -> bool {
+    repo.name.as_deref() == Some(pattern) || repo.url.trim_end_matches('/') == pattern.trim_end_matches('/')
+}
+
+fn is_local(repo: &Repository) -> bool {
+    let Some((scheme, rest)) = repo.url.split_once("://") else {
+        return false;
+    };
+
+    let host = rest.split(['/', ':']).next().unwrap_or_default();
+
+    scheme == "file" || host == "localhost" || host == "127.0.0.1"
+}
+
+/// Путь на диске для file:// URL. file:///C:/repo на Windows даёт C:/repo
+pub fn file_url_path(url: &str) -> Option<PathBuf> {
+    let path = url.strip_prefix("file://")?;
+
+    let path = match path.strip_prefix('/') {
+        Some(rest) if rest.as_bytes().get(1) == Some(&b':') => rest,
+        _ => path,
+    };
+
+    Some(PathBuf::from(path))
+}
+
+/// Запись в ~/.spawn/credentials.toml:
+///
+/// [nexus]
+/// username = "ci"
+/// password = "..."
+///
+/// [github]
+/// token = "..."
+#[derive(Debug, Deserialize, Default)]
+struct CredentialsEntry {
+    username: Option<String>,
+    password: Option<String>,
+    token: Option<String>,
+}
+
+/// Учётные данные репозитория. Переменные окружения SPAWN_<NAME>_TOKEN,
+/// SPAWN_<NAME>_USERNAME и SPAWN_<NAME>_PASSWORD (имя в верхнем регистре,
+/// всё кроме букв и цифр заменяется на _) важнее чем credentials.toml
+fn credentials(name: &str) -> Result<Option<Auth>, String> {
+    let prefix = format!("SPAWN_{}_", env_name(name));
+    let var = |key: &str| env::var(format!("{}{}", prefix, key)).ok().filter(|v| !v.is_empty());
+
+    let mut entry = CredentialsEntry {
+        username: var("USERNAME"),
+        password: var("PASSWORD"),
+        token: var("TOKEN"),
+    };
+
+    if entry.username.is_none() && entry.token.is_none() {
+        entry = load_credentials_file()?.remove(name).unwrap_or_default();
+    }
+
+    let auth = match entry {
+        CredentialsEntry { token: Some(token), .. } => Some(Auth::Bearer(token)),
+        CredentialsEntry { username: Some(username), password, .. } => Some(Auth::Basic { username, password }),
+        _ => None,
+    };
+
+    match &auth {
+        Some(Auth::Bearer(token)) => logger::add_secret(token),
+        Some(Auth::Basic { password: Some(password), .. }) => logger::add_secret(password),
+        _ => {}
+    }
+
+    Ok(auth)
+}
+
+fn load_credentials_file() -> Result<HashMap<String, CredentialsEntry>, String> {
+    let Some(path) = credentials_path() else {
+        return Ok(HashMap::new());
+    };
+
+    if !path.exists() {
+        return Ok(HashMap::new());
+    }
+
+    let content = fs::read_to_string(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+
+    // Текст ошибки toml содержит строку файла, а в ней может быть пароль
+    toml::from_str(&content).map_err(|_| format!("Invalid {}", path.display()))
+}
+
+fn credentials_path() -> Option<PathBuf> {
+    home_dir().map(|h| h.join(".spawn").join(CREDENTIALS_FILE))
+}
+
+fn env_name(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_uppercase() } else { '_' })
+        .collect()
+}
@@ -0,0 +1,270 @@
+// Copyright (c) 2025 Spawn
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// https://www.eclipse.org/legal/epl-2.0/
+// SPDX-License-Identifier: EPL-2.0
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::Deserialize;
+
+use crate::resolver::artifact::{group_matches, Artifact};
+
+/// Проверка PGP подписей (.asc) скачанных артефактов
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SignaturePolicy {
+    /// Подписи не проверяются
+    #[default]
+    Off,
+
+    /// Неподписанные и недоверенные артефакты попадают в отчёт как предупреждения
+    Warn,
+
+    /// Любой неподписанный или недоверенный артефакт это ошибка сборки
+    Strict,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SignatureStatus {
+    /// Подпись верна и ключ доверенный для группы артефакта
+    Trusted(String),
+
+    /// Репозиторий не публикует .asc для файла
+    Unsigned,
+
+    /// Подпись сделана ключом которого нет в файле доверенных ключей
+    UnknownKey(String),
+
+    /// Подпись верна, но ключ не указан как доверенный для этой группы
+    Untrusted(String),
+
+    /// Ключ или сама подпись просрочены. gpg при этом всё равно пишет VALIDSIG
+    Expired(String),
+
+    /// Ключ отозван владельцем
+    Revoked(String),
+
+    /// Подпись не сходится с файлом
+    Bad(String),
+}
+
+impl fmt::Display for SignatureStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SignatureStatus::Trusted(fpr) => write!(f, "trusted ({})", fpr),
+            SignatureStatus::Unsigned => write!(f, "unsigned"),
+            SignatureStatus::UnknownKey(id) => write!(f, "signed by unknown key {}", id),
+            SignatureStatus::Untrusted(fpr) => write!(f, "signed by {} which is not trusted for this group", fpr),
+            SignatureStatus::Expired(id) => write!(f, "signature or key {} has expired", id),
+            SignatureStatus::Revoked(id) => write!(f, "signed by revoked key {}", id),
+            SignatureStatus::Bad(reason) => write!(f, "bad signature: {}", reason),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SignatureEntry {
+    pub artifact: Artifact,
+    pub file: PathBuf,
+    pub status: SignatureStatus,
+}
+
+/// Отчёт resolver о подписях всех проверенных файлов
+#[derive(Debug, Default)]
+pub struct SignatureReport {
+    pub entries: Vec<SignatureEntry>,
+}
+
+impl SignatureReport {
+    /// Всё кроме доверенных подписей
+    pub fn problems(&self) -> impl Iterator<Item = &SignatureEntry> {
+        self.entries.iter().filter(|e| !matches!(e.status, SignatureStatus::Trusted(_)))
+    }
+}
+
+/// Проверяет подписи через gpg с отдельной временной домашней папкой, в которую
+/// импортируются только ключи проекта. Личный keyring пользователя не используется
+pub struct SignatureVerifier {
+    home: PathBuf,
+
+    /// Шаблон группы (androidx, com.google.*, *) и доверенные отпечатки
+    trusted: Vec<(String, Vec<String>)>,
+}
+
+impl SignatureVerifier {
+    pub fn new(keys_file: &Path, trusted: &HashMap<String, Vec<String>>) -> Result<Self, String> {
+        if !keys_file.exists() {
+            return Err(format!("Trusted keys file not found: {}", keys_file.display()));
+        }
+
+        let home = create_home()?;
+
+        let output = Command::new("gpg")
+            .arg("--homedir").arg(&home)
+            .args(["--batch", "--quiet", "--import"])
+            .arg(keys_file)
+            .stdout(Stdio::null())
+            .output()
+            .map_err(|e| format!("Failed to run gpg: {}", e))?;
+
+        if !output.status.success() {
+            fs::remove_dir_all(&home).ok();
+
+            return Err(format!(
+                "Failed to import {}: {}",
+                keys_file.display(),
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+
+        let mut trusted: Vec<(String, Vec<String>)> = trusted.iter()
+            .map(|(group, fprs)| (group.clone(), fprs.iter().map(|f| normalize_fingerprint(f)).collect()))
+            .collect();
+
+        // Более конкретные шаблоны проверяются первыми
+        trusted.sort_by_key(|(pattern, _)| std::cmp::Reverse(pattern.len()));
+
+        Ok(Self { home, trusted })
+    }
+
+    /// Проверяет подпись файла. Если .asc рядом нет, то файл считается неподписанным
+    pub fn verify(&self, art: &Artifact, file: &Path, signature: &Path) -> SignatureStatus {
+        if !signature.exists() {
+            return SignatureStatus::Unsigned;
+        }
+
+        let output = Command::new("gpg")
+            .arg("--homedir").arg(&self.home)
+            .args(["--batch", "--status-fd", "1", "--verify"])
+            .arg(signature)
+            .arg(file)
+            .stderr(Stdio::null())
+            .output();
+
+        let output = match output {
+            Ok(o) => o,
+            Err(e) => return SignatureStatus::Bad(format!("failed to run gpg: {}", e)),
+        };
+
+        let status = String::from_utf8_lossy(&output.stdout);
+
+        let mut valid = None;
+        let mut expired = None;
+        let mut revoked = None;
+        let mut unknown_key = None;
+
+        // VALIDSIG приходит и для просроченных и отозванных ключей, поэтому
+        // решение принимается только после просмотра всех строк
+        for line in status.lines() {
+            let mut parts = line.split_whitespace();
+
+            if parts.next() != Some("[GNUPG:]") {
+                continue;
+            }
+
+            match parts.next() {
+                // VALIDSIG <fpr> ... <primary-fpr>, доверие проверяется по основному ключу
+                Some("VALIDSIG") => {
+                    valid = parts.last().map(|k| k.to_string());
+                }
+
+                Some("BADSIG") => {
+                    return SignatureStatus::Bad(parts.collect::<Vec<_>>().join(" "));
+                }
+
+                Some("EXPSIG" | "EXPKEYSIG") => {
+                    expired = Some(parts.next().unwrap_or_default().to_string());
+                }
+
+                Some("REVKEYSIG") => {
+                    revoked = Some(parts.next().unwrap_or_default().to_string());
+                }
+
+                // Без аргументов, ключ берётся из VALIDSIG
+                Some("KEYREVOKED") => {
+                    revoked.get_or_insert_with(String::new);
+                }
+
+                Some("NO_PUBKEY") => {
+                    unknown_key = parts.next().map(|k| k.to_string());
+                }
+
+                _ => {}
+            }
+        }
+
+        let key = |id: String| if id.is_empty() { valid.clone().unwrap_or_default() } else { id };
+
+        if let Some(id) = revoked {
+            return SignatureStatus::Revoked(key(id));
+        }
+
+        if let Some(id) = expired {
+            return SignatureStatus::Expired(key(id));
+        }
+
+        if let Some(primary) = valid {
+            return if self.is_trusted(&art.group, &primary) {
+                SignatureStatus::Trusted(primary)
+            } else {
+                SignatureStatus::Untrusted(primary)
+            };
+        }
+
+        match unknown_key {
+            Some(key) => SignatureStatus::UnknownKey(key),
+            None => SignatureStatus::Bad("gpg could not verify the signature".to_string()),
+        }
+    }
+
+    fn is_trusted(&self, group: &str, fingerprint: &str) -> bool {
+        let fingerprint = normalize_fingerprint(fingerprint);
+
+        self.trusted.iter()
+            .find(|(pattern, _)| group_matches(pattern, group))
+            .is_some_and(|(_, fprs)| fprs.contains(&fingerprint))
+    }
+}
+
+impl Drop for SignatureVerifier {
+    fn drop(&mut self) {
+        fs::remove_dir_all(&self.home).ok();
+    }
+}
+
+/// Создаёт домашнюю папку gpg во временной папке. Имя не угадать заранее, а
+/// уже существующая папка это ошибка: чужой keyring подменил бы доверенные ключи
+fn create_home() -> Result<PathBuf, String> {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or_default();
+
+    let home = std::env::temp_dir().join(format!("spawn-gpg-{}-{:x}", std::process::id(), nanos));
+
+    let mut builder = fs::DirBuilder::new();
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::DirBuilderExt;
+        builder.mode(0o700);
+    }
+
+    builder.create(&home)
+        .map_err(|e| format!("Failed to create gpg home {}: {}", home.display(), e))?;
+
+    Ok(home)
+}
+
+fn normalize_fingerprint(fpr: &str) -> String {
+    fpr.chars()
+        .filter(|c| !c.is_whitespace())
+        .collect::<String>()
+        .to_ascii_uppercase()
+}
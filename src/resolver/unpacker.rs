@@ -10,10 +10,22 @@ use std::path::Path;
 use zip::ZipArchive;
 
 pub fn unpack_aar(aar_path: &Path) -> Result<std::path::PathBuf, Box<dyn std::error::Error>> {
-    let out_dir = aar_path.parent().unwrap().join("unpacked");
+    unpack_aar_into(aar_path, &aar_path.parent().unwrap().join("unpacked"))
+}
+
+/// Распаковывает aar в out_dir. Если aar новее распакованной папки (его
+/// пересобрали), то папка распаковывается заново
+pub fn unpack_aar_into(aar_path: &Path, out_dir: &Path) -> Result<std::path::PathBuf, Box<dyn std::error::Error>> {
+    let out_dir = out_dir.to_path_buf();
 
     if out_dir.exists() {
-        return Ok(out_dir);
+        let modified = |p: &Path| fs::metadata(p).and_then(|m| m.modified()).ok();
+
+        if modified(aar_path) <= modified(&out_dir) {
+            return Ok(out_dir);
+        }
+
+        fs::remove_dir_all(&out_dir)?;
     }
 
     let file = fs::File::open(aar_path)?;
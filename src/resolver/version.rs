@@ -0,0 +1,529 @@
+// Copyright (c) 2025 Spawn
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// https://www.eclipse.org/legal/epl-2.0/
+// SPDX-License-Identifier: EPL-2.0
+
+use std::cmp::Ordering;
+use std::fmt;
+
+/// Квалификаторы в порядке возрастания, "" это релиз. Неизвестные
+/// квалификаторы старше всех известных и сравниваются как строки
+const QUALIFIERS: &[&str] = &["alpha", "beta", "milestone", "rc", "snapshot", "", "sp"];
+
+/// Версия с порядком как у ComparableVersion из Maven:
+/// 1.0-alpha1 < 1.0-beta < 1.0-m1 < 1.0-rc1 < 1.0-SNAPSHOT < 1.0 < 1.0-sp1,
+/// 1.9 < 1.10, 1 == 1.0 == 1.0.0, регистр не важен
+#[derive(Debug, Clone)]
+pub struct ComparableVersion {
+    original: String,
+    items: Vec<Item>,
+}
+
+#[derive(Debug, Clone)]
+enum Item {
+    /// Число без ведущих нулей, длина не ограничена
+    Int(String),
+
+    /// Квалификатор после замены синонимов (cr -> rc, ga -> "")
+    Str(String),
+
+    /// Всё что идёт после "-" или на границе цифр и букв
+    List(Vec<Item>),
+}
+
+impl ComparableVersion {
+    pub fn new(version: &str) -> Self {
+        Self {
+            original: version.to_string(),
+            items: parse(&version.to_lowercase()),
+        }
+    }
+}
+
+/// Сравнение двух версий по правилам Maven
+pub fn compare_versions(a: &str, b: &str) -> Ordering {
+    ComparableVersion::new(a).cmp(&ComparableVersion::new(b))
+}
+
+impl Ord for ComparableVersion {
+    fn cmp(&self, other: &Self) -> Ordering {
+        compare_lists(&self.items, &other.items)
+    }
+}
+
+impl PartialOrd for ComparableVersion {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for ComparableVersion {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for ComparableVersion {}
+
+impl fmt::Display for ComparableVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.original)
+    }
+}
+
+/// Диапазон версий Maven: [1.0], [1.0,2.0), (,1.0], [1.2,), а также
+/// объединение [1.0,2.0),[3.0,). Версия без скобок ("1.0") это мягкое
+/// требование, уступающее другим версиям, диапазон это жёсткое ограничение
+#[derive(Debug, Clone)]
+pub struct VersionRange {
+    original: String,
+    restrictions: Vec<Restriction>,
+}
+
+#[derive(Debug, Clone)]
+struct Restriction {
+    lower: Option<ComparableVersion>,
+    lower_inclusive: bool,
+    upper: Option<ComparableVersion>,
+    upper_inclusive: bool,
+}
+
+impl VersionRange {
+    /// Является ли строка диапазоном (жёстким требованием)
+    pub fn is_range(spec: &str) -> bool {
+        spec.trim_start().starts_with(['[', '('])
+    }
+
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let mut restrictions: Vec<Restriction> = Vec::new();
+        let mut rest = spec.trim();
+
+        while rest.starts_with(['[', '(']) {
+            let Some(end) = rest.find([')', ']']) else {
+                return Err("unbounded range".to_string());
+            };
+
+            let restriction = Restriction::parse(&rest[..=end])?;
+
+            // Интервалы должны идти по возрастанию и не пересекаться
+            if let Some(previous) = restrictions.last()
+                && match (&previous.upper, &restriction.lower) {
+                    (Some(upper), Some(lower)) => lower < upper,
+                    _ => true,
+                }
+            {
+                return Err("ranges overlap".to_string());
+            }
+
+            restrictions.push(restriction);
+
+            rest = rest[end + 1..].trim_start();
+            rest = rest.strip_prefix(',').unwrap_or(rest).trim_start();
+        }
+
+        if !rest.is_empty() || restrictions.is_empty() {
+            return Err("expected [a,b), (a,b], [a] or a union of them".to_string());
+        }
+
+        Ok(Self {
+            original: spec.trim().to_string(),
+            restrictions,
+        })
+    }
+
+    pub fn contains(&self, version: &str) -> bool {
+        let version = ComparableVersion::new(version);
+        self.restrictions.iter().any(|r| r.contains(&version))
+    }
+
+    /// Версия если диапазон это ровно одна версия: [1.6.1]
+    pub fn exact(&self) -> Option<&str> {
+        match self.restrictions.as_slice() {
+            [Restriction { lower: Some(lower), upper: Some(upper), .. }] if lower.original == upper.original => {
+                Some(&lower.original)
+            }
+
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for VersionRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.original)
+    }
+}
+
+impl Restriction {
+    fn parse(spec: &str) -> Result<Self, String> {
+        let lower_inclusive = spec.starts_with('[');
+        let upper_inclusive = spec.ends_with(']');
+        let inner = spec[1..spec.len() - 1].trim();
+
+        let Some((lower, upper)) = inner.split_once(',') else {
+            // [1.0] это ровно 1.0, а (1.0) не имеет смысла
+            if !lower_inclusive || !upper_inclusive || inner.is_empty() {
+                return Err(format!("single version must be surrounded by []: {}", spec));
+            }
+
+            let version = ComparableVersion::new(inner);
+
+            return Ok(Self {
+                lower: Some(version.clone()),
+                lower_inclusive,
+                upper: Some(version),
+                upper_inclusive,
+            });
+        };
+
+        let bound = |v: &str| Some(v.trim()).filter(|v| !v.is_empty()).map(ComparableVersion::new);
+        let (lower, upper) = (bound(lower), bound(upper));
+
+        if let (Some(lower), Some(upper)) = (&lower, &upper)
+            && upper < lower
+        {
+            return Err(format!("range defies version ordering: {}", spec));
+        }
+
+        Ok(Self {
+            lower,
+            lower_inclusive,
+            upper,
+            upper_inclusive,
+        })
+    }
+
+    fn contains(&self, version: &ComparableVersion) -> bool {
+        let above = match &self.lower {
+            Some(lower) => version > lower || (self.lower_inclusive && version == lower),
+            None => true,
+        };
+
+        let below = match &self.upper {
+            Some(upper) => version < upper || (self.upper_inclusive && version == upper),
+            None => true,
+        };
+
+        above && below
+    }
+}
+
+fn parse(version: &str) -> Vec<Item> {
+    // Вершина стека это список в который сейчас добавляются элементы.
+    // Вложенный список всегда последний элемент родителя
+    let mut stack: Vec<Vec<Item>> = vec![Vec::new()];
+    let mut is_digit = false;
+    let mut start = 0;
+
+    for (i, c) in version.char_indices() {
+        let list = stack.last_mut().unwrap();
+
+        match c {
+            '.' | '-' => {
+                if i == start {
+                    list.push(Item::Int(String::new()));
+                } else {
+                    list.push(item(is_digit, &version[start..i]));
+                }
+
+                start = i + 1;
+
+                if c == '-' {
+                    stack.push(Vec::new());
+                }
+            }
+
+            c if c.is_ascii_digit() => {
+                // Переход от букв к цифрам: alpha1 это alpha-1
+                if !is_digit && i > start {
+                    list.push(Item::Str(qualifier(&version[start..i], true)));
+                    start = i;
+                    stack.push(Vec::new());
+                }
+
+                is_digit = true;
+            }
+
+            _ => {
+                // Переход от цифр к буквам: 1a это 1-a
+                if is_digit && i > start {
+                    list.push(item(true, &version[start..i]));
+                    start = i;
+                    stack.push(Vec::new());
+                }
+
+                is_digit = false;
+            }
+        }
+    }
+
+    if version.len() > start {
+        stack.last_mut().unwrap().push(item(is_digit, &version[start..]));
+    }
+
+    while stack.len() > 1 {
+        let mut list = stack.pop().unwrap();
+        normalize(&mut list);
+        stack.last_mut().unwrap().push(Item::List(list));
+    }
+
+    let mut items = stack.pop().unwrap();
+    normalize(&mut items);
+    items
+}
+
+fn item(is_digit: bool, value: &str) -> Item {
+    if is_digit {
+        Item::Int(value.trim_start_matches('0').to_string())
+    } else {
+        Item::Str(qualifier(value, false))
+    }
+}
+
+/// Синонимы квалификаторов. a, b и m сокращения только если за ними цифра (a1)
+fn qualifier(value: &str, followed_by_digit: bool) -> String {
+    let value = match value {
+        "a" if followed_by_digit => "alpha",
+        "b" if followed_by_digit => "beta",
+        "m" if followed_by_digit => "milestone",
+        "ga" | "final" | "release" => "",
+        "cr" => "rc",
+        other => other,
+    };
+
+    value.to_string()
+}
+
+/// Убирает нулевые элементы в конце (0, "", пустой список): 1.0.0 это 1
+fn normalize(list: &mut Vec<Item>) {
+    for i in (0..list.len()).rev() {
+        if is_null(&list[i]) {
+            list.remove(i);
+        } else if !matches!(list[i], Item::List(_)) {
+            break;
+        }
+    }
+}
+
+fn is_null(item: &Item) -> bool {
+    match item {
+        Item::Int(digits) => digits.is_empty(),
+        Item::Str(value) => value.is_empty(),
+        Item::List(list) => list.is_empty(),
+    }
+}
+
+/// Позиция квалификатора для сравнения строкой: известные "0".."6",
+/// неизвестные "7-<имя>", поэтому они старше sp и упорядочены по алфавиту
+fn comparable_qualifier(value: &str) -> String {
+    match QUALIFIERS.iter().position(|q| *q == value) {
+        Some(i) => i.to_string(),
+        None => format!("{}-{}", QUALIFIERS.len(), value),
+    }
+}
+
+/// Сравнение элемента с отсутствующим (когда одна версия длиннее другой)
+fn compare_to_null(item: &Item) -> Ordering {
+    match item {
+        Item::Int(digits) => if digits.is_empty() { Ordering::Equal } else { Ordering::Greater },
+        Item::Str(value) => comparable_qualifier(value).cmp(&comparable_qualifier("")),
+        Item::List(list) => list.first().map(compare_to_null).unwrap_or(Ordering::Equal),
+    }
+}
+
+fn compare_items(a: &Item, b: &Item) -> Ordering {
+    match (a, b) {
+        (Item::Int(a), Item::Int(b)) => a.len().cmp(&b.len()).then_with(|| a.cmp(b)),
+
+        // 1.1 > 1-sp и 1.1 > 1-1
+        (Item::Int(_), _) => Ordering::Greater,
+        (_, Item::Int(_)) => Ordering::Less,
+
+        (Item::Str(a), Item::Str(b)) => comparable_qualifier(a).cmp(&comparable_qualifier(b)),
+
+        // 1-a < 1-1
+        (Item::Str(_), Item::List(_)) => Ordering::Less,
+        (Item::List(_), Item::Str(_)) => Ordering::Greater,
+
+        (Item::List(a), Item::List(b)) => compare_lists(a, b),
+    }
+}
+
+fn compare_lists(a: &[Item], b: &[Item]) -> Ordering {
+    for i in 0..a.len().max(b.len()) {
+        let result = match (a.get(i), b.get(i)) {
+            (Some(a), Some(b)) => compare_items(a, b),
+            (Some(a), None) => compare_to_null(a),
+            (None, Some(b)) => compare_to_null(b).reverse(),
+            (None, None) => Ordering::Equal,
+        };
+
+        if result != Ordering::Equal {
+            return result;
+        }
+    }
+
+    Ordering::Equal
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Каждая версия строго больше предыдущей (и все пары проверяются)
+    fn check_ordered(versions: &[&str]) {
+        for (i, a) in versions.iter().enumerate() {
+            for b in &versions[i + 1..] {
+                assert_eq!(compare_versions(a, b), Ordering::Less, "{} < {}", a, b);
+                assert_eq!(compare_versions(b, a), Ordering::Greater, "{} > {}", b, a);
+            }
+        }
+    }
+
+    fn check_equal(a: &str, b: &str) {
+        assert_eq!(compare_versions(a, b), Ordering::Equal, "{} == {}", a, b);
+        assert_eq!(compare_versions(b, a), Ordering::Equal, "{} == {}", b, a);
+    }
+
+    #[test]
+    fn qualifier_order() {
+        check_ordered(&[
+            "1-alpha2snapshot", "1-alpha2", "1-alpha-123", "1-beta-2", "1-beta123", "1-m2", "1-m11", "1-rc",
+            "1-cr2", "1-rc123", "1-SNAPSHOT", "1", "1-sp", "1-sp2", "1-sp123", "1-abc", "1-def", "1-pom-1",
+            "1-1-snapshot", "1-1", "1-2", "1-123",
+        ]);
+    }
+
+    #[test]
+    fn number_order() {
+        check_ordered(&[
+            "2.0", "2-1", "2.0.a", "2.0.0.a", "2.0.2", "2.0.123", "2.1.0", "2.1-a", "2.1b", "2.1-c", "2.1-1",
+            "2.1.0.1", "2.2", "2.123", "11.a2", "11.a11", "11.b2", "11.b11", "11.m2", "11.m11", "11", "11.a",
+            "11b", "11c", "11m",
+        ]);
+    }
+
+    #[test]
+    fn trailing_zeros_are_ignored() {
+        check_equal("1", "1.0");
+        check_equal("1", "1.0.0");
+        check_equal("1.0", "1.0.0");
+        check_equal("1", "1-0");
+        check_equal("1", "1.0-0");
+        check_equal("1.0", "1.0-0");
+    }
+
+    #[test]
+    fn letters_after_numbers() {
+        check_equal("1a", "1-a");
+        check_equal("1a", "1.0-a");
+        check_equal("1a", "1.0.0-a");
+        check_equal("1.0a", "1-a");
+        check_equal("1.0.0a", "1-a");
+        check_equal("1x", "1-x");
+        check_equal("1x", "1.0-x");
+        check_equal("1.0.0x", "1-x");
+    }
+
+    #[test]
+    fn aliases() {
+        check_equal("1ga", "1");
+        check_equal("1release", "1");
+        check_equal("1final", "1");
+        check_equal("1-ga", "1.0");
+        check_equal("1cr", "1rc");
+        check_equal("1a1", "1-alpha-1");
+        check_equal("1b2", "1-beta-2");
+        check_equal("1m3", "1-milestone-3");
+    }
+
+    #[test]
+    fn case_insensitive() {
+        check_equal("1X", "1x");
+        check_equal("1A", "1a");
+        check_equal("1-SNAPSHOT", "1-snapshot");
+        check_equal("1-Alpha-1", "1-alpha-1");
+        check_equal("1.0.0.RELEASE", "1.0.0");
+    }
+
+    #[test]
+    fn leading_zeros_and_big_numbers() {
+        check_equal("1.01", "1.1");
+        check_equal("1.0.0-alpha01", "1.0.0-alpha1");
+        check_ordered(&["1.9", "1.10", "1.99999999999999999999", "1.100000000000000000000"]);
+    }
+
+    #[test]
+    fn android_versions() {
+        check_ordered(&[
+            "1.0.0-alpha01", "1.0.0-alpha02", "1.0.0-alpha10", "1.0.0-beta01", "1.0.0-rc01", "1.0.0-rc02",
+            "1.0.0", "1.0.1",
+        ]);
+
+        check_ordered(&["2.0-rc1", "2.0"]);
+        check_ordered(&["1.9-beta", "1.10"]);
+        check_ordered(&["1.9", "1.10-beta", "1.10"]);
+    }
+
+    #[test]
+    fn ranges() {
+        let range = VersionRange::parse("[1.0,2.0)").unwrap();
+        assert!(range.contains("1.0"));
+        assert!(range.contains("1.9.9"));
+        assert!(range.contains("2.0-rc1"));
+        assert!(!range.contains("2.0"));
+        assert!(!range.contains("0.9"));
+
+        let range = VersionRange::parse("(,1.0]").unwrap();
+        assert!(range.contains("0.1"));
+        assert!(range.contains("1.0"));
+        assert!(!range.contains("1.0.1"));
+
+        let range = VersionRange::parse("[1.5,)").unwrap();
+        assert!(range.contains("1.5"));
+        assert!(range.contains("100"));
+        assert!(!range.contains("1.4"));
+    }
+
+    #[test]
+    fn range_unions() {
+        let range = VersionRange::parse("[1.0,2.0),[3.0,)").unwrap();
+        assert!(range.contains("1.5"));
+        assert!(!range.contains("2.5"));
+        assert!(range.contains("3.0"));
+        assert!(range.contains("4.1"));
+
+        let range = VersionRange::parse("(,1.0],[1.2,)").unwrap();
+        assert!(range.contains("1.0"));
+        assert!(!range.contains("1.1"));
+        assert!(range.contains("1.2"));
+    }
+
+    #[test]
+    fn exact_ranges() {
+        let range = VersionRange::parse("[1.6.1]").unwrap();
+        assert_eq!(range.exact(), Some("1.6.1"));
+        assert!(range.contains("1.6.1"));
+        assert!(!range.contains("1.6.2"));
+
+        assert_eq!(VersionRange::parse("[1.0,2.0)").unwrap().exact(), None);
+        assert!(!VersionRange::is_range("1.6.1"));
+        assert!(VersionRange::is_range("[1.6.1]"));
+    }
+
+    #[test]
+    fn invalid_ranges() {
+        for spec in ["[1.0,2.0", "(1.0)", "[2.0,1.0]", "[1.0,3.0),[2.0,4.0)", "(,1.0],(,2.0]", "[1.0,2.0),3.0", "1.0", "[]"] {
+            assert!(VersionRange::parse(spec).is_err(), "{} is invalid", spec);
+        }
+    }
+
+    #[test]
+    fn max_picks_release_over_prerelease() {
+        let versions = ["1.0.0", "1.0.0-rc01", "1.0.0-beta02", "0.9"];
+        let max = versions.iter().map(|v| ComparableVersion::new(v)).max().unwrap();
+
+        assert_eq!(max.to_string(), "1.0.0");
+    }
+}